@@ -0,0 +1,206 @@
+use macroquad::{color::Color, color_u8};
+
+use crate::structs::*;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Biome {
+    Temperate,
+    Archipelago,
+    Highlands,
+    Desert,
+    Urban,
+}
+
+pub const BIOMES: [Biome; 5] = [
+    Biome::Temperate,
+    Biome::Archipelago,
+    Biome::Highlands,
+    Biome::Desert,
+    Biome::Urban,
+];
+
+pub struct Palette {
+    pub grass: Color,
+    pub wall: (Color, Color),
+    pub road: (Color, Color),
+    pub hills: [(Color, Color); 3],
+    pub water: [Color; 3],
+    pub water_border: Color,
+    pub shallow: (Color, Color),
+}
+
+pub struct Preset {
+    pub frequency: f64,
+    pub octaves: usize,
+    pub water: f64,
+    pub shallow: f64,
+    pub hills: [f64; 3],
+    pub towns: i32,
+    pub town_radius: u32,
+    pub palette: Palette,
+}
+
+const TEMPERATE: Preset = Preset {
+    frequency: 0.1,
+    octaves: 1,
+    water: -0.65,
+    shallow: -0.5,
+    hills: [0.4, 0.6, 0.8],
+    towns: 40,
+    town_radius: 8,
+    palette: Palette {
+        grass: GRASS_COLOR,
+        wall: (WALL_COLOR, WALL_BORDER_COLOR),
+        road: (ROAD_COLOR, ROAD_BORDER_COLOR),
+        hills: [
+            (HILL_LOW_COLOR, HILL_LOW_BORDER_COLOR),
+            (HILL_MEDIUM_COLOR, HILL_MEDIUM_BORDER_COLOR),
+            (HILL_HIGH_COLOR, HILL_HIGH_BORDER_COLOR),
+        ],
+        water: [WATER_COLOR, WATER_COLOR_ALT1, WATER_COLOR_ALT2],
+        water_border: WATER_BORDER_COLOR,
+        shallow: (SHALLOW_COLOR, SHALLOW_BORDER_COLOR),
+    },
+};
+
+const ARCHIPELAGO: Preset = Preset {
+    frequency: 0.05,
+    octaves: 3,
+    water: -0.1,
+    shallow: 0.05,
+    hills: [0.45, 0.6, 0.75],
+    towns: 30,
+    town_radius: 6,
+    palette: Palette {
+        grass: color_u8!(120, 170, 70, 255),
+        wall: (color_u8!(150, 130, 100, 255), color_u8!(110, 95, 70, 255)),
+        road: (color_u8!(200, 185, 140, 255), color_u8!(165, 150, 105, 255)),
+        hills: [
+            (color_u8!(100, 150, 65, 255), color_u8!(75, 115, 45, 255)),
+            (color_u8!(85, 130, 55, 255), color_u8!(60, 95, 40, 255)),
+            (color_u8!(70, 110, 45, 255), color_u8!(50, 80, 30, 255)),
+        ],
+        water: [
+            color_u8!(20, 120, 190, 255),
+            color_u8!(30, 130, 200, 255),
+            color_u8!(10, 110, 180, 255),
+        ],
+        water_border: color_u8!(10, 95, 150, 255),
+        shallow: (color_u8!(90, 200, 210, 255), color_u8!(60, 170, 185, 255)),
+    },
+};
+
+const HIGHLANDS: Preset = Preset {
+    frequency: 0.08,
+    octaves: 4,
+    water: -0.75,
+    shallow: -0.65,
+    hills: [0.1, 0.3, 0.5],
+    towns: 20,
+    town_radius: 6,
+    palette: Palette {
+        grass: color_u8!(110, 140, 75, 255),
+        wall: (color_u8!(95, 90, 85, 255), color_u8!(70, 65, 60, 255)),
+        road: (color_u8!(140, 120, 90, 255), color_u8!(105, 90, 65, 255)),
+        hills: [
+            (color_u8!(120, 125, 80, 255), color_u8!(90, 95, 60, 255)),
+            (color_u8!(115, 105, 80, 255), color_u8!(85, 75, 55, 255)),
+            (color_u8!(130, 125, 120, 255), color_u8!(95, 90, 85, 255)),
+        ],
+        water: [WATER_COLOR, WATER_COLOR_ALT1, WATER_COLOR_ALT2],
+        water_border: WATER_BORDER_COLOR,
+        shallow: (SHALLOW_COLOR, SHALLOW_BORDER_COLOR),
+    },
+};
+
+const DESERT: Preset = Preset {
+    frequency: 0.06,
+    octaves: 2,
+    water: -0.85,
+    shallow: -0.75,
+    hills: [0.35, 0.55, 0.75],
+    towns: 50,
+    town_radius: 7,
+    palette: Palette {
+        grass: color_u8!(215, 190, 130, 255),
+        wall: (color_u8!(170, 130, 90, 255), color_u8!(130, 95, 60, 255)),
+        road: (color_u8!(185, 160, 115, 255), color_u8!(150, 125, 85, 255)),
+        hills: [
+            (color_u8!(205, 170, 110, 255), color_u8!(175, 140, 85, 255)),
+            (color_u8!(190, 150, 95, 255), color_u8!(160, 120, 70, 255)),
+            (color_u8!(170, 125, 80, 255), color_u8!(140, 100, 60, 255)),
+        ],
+        water: [
+            color_u8!(40, 150, 150, 255),
+            color_u8!(50, 160, 160, 255),
+            color_u8!(30, 140, 140, 255),
+        ],
+        water_border: color_u8!(25, 115, 115, 255),
+        shallow: (color_u8!(100, 185, 165, 255), color_u8!(75, 155, 140, 255)),
+    },
+};
+
+const URBAN: Preset = Preset {
+    frequency: 0.1,
+    octaves: 1,
+    water: -0.7,
+    shallow: -0.6,
+    hills: [0.55, 0.7, 0.85],
+    towns: 100,
+    town_radius: 10,
+    palette: Palette {
+        grass: color_u8!(125, 135, 115, 255),
+        wall: (color_u8!(70, 70, 75, 255), color_u8!(50, 50, 55, 255)),
+        road: (color_u8!(105, 105, 110, 255), color_u8!(80, 80, 85, 255)),
+        hills: [
+            (color_u8!(110, 125, 95, 255), color_u8!(85, 100, 70, 255)),
+            (color_u8!(100, 110, 85, 255), color_u8!(75, 85, 60, 255)),
+            (color_u8!(90, 95, 80, 255), color_u8!(65, 70, 55, 255)),
+        ],
+        water: [
+            color_u8!(50, 110, 140, 255),
+            color_u8!(60, 120, 150, 255),
+            color_u8!(40, 100, 130, 255),
+        ],
+        water_border: color_u8!(35, 85, 110, 255),
+        shallow: (color_u8!(90, 145, 165, 255), color_u8!(65, 120, 140, 255)),
+    },
+};
+
+impl Biome {
+    pub fn preset(&self) -> &'static Preset {
+        match self {
+            Biome::Temperate => &TEMPERATE,
+            Biome::Archipelago => &ARCHIPELAGO,
+            Biome::Highlands => &HIGHLANDS,
+            Biome::Desert => &DESERT,
+            Biome::Urban => &URBAN,
+        }
+    }
+
+    pub fn palette(&self) -> &'static Palette {
+        &self.preset().palette
+    }
+
+    pub fn text(&self) -> usize {
+        60 + *self as usize
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Biome::Temperate => "temperate",
+            Biome::Archipelago => "archipelago",
+            Biome::Highlands => "highlands",
+            Biome::Desert => "desert",
+            Biome::Urban => "urban",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        BIOMES.into_iter().find(|biome| biome.name() == name)
+    }
+
+    pub fn next(&self) -> Self {
+        BIOMES[(*self as usize + 1) % BIOMES.len()]
+    }
+}
@@ -1,31 +1,30 @@
-use macroquad::{math::IVec2, rand::gen_range};
+use macroquad::math::IVec2;
 
 use crate::{
+    hex,
     level::Level,
     manager::Manager,
-    squad::Squad,
+    squad::{self, Attack, Hit, Shell, Squad},
     structs::{Difficulty, Team, UnitType},
     unit::Unit,
 };
 
 pub struct Bot {
     squads: Vec<Squad>,
+    shells: Vec<Shell>,
 }
 
 impl Bot {
     pub fn new(level: &mut Level, difficulty: Difficulty) -> Self {
-        let coef = difficulty.clone() as usize + 1;
-        let goals = level.find_captures();
-
-        let mut base = &IVec2::ZERO;
-        while base == &IVec2::ZERO {
-            base = &goals[gen_range(0, goals.len() - 1)];
-        }
-
-        level.capture(base, &Team::Computer);
+        let coef = difficulty as usize + 1;
+        let base = level.base(&Team::Computer);
+        let mut goals = level.find_captures();
+        goals.sort_by_key(|goal| hex::distance(goal, &base));
+        level.capture(&base, &Team::Computer);
 
         Self {
-            squads: (0..coef * goals.len())
+            shells: Vec::new(),
+            squads: (0..coef * level.size().dimensions().squads)
                 .map(|i| {
                     let mut t = Squad::new(
                         base.with_y(base.y + i as i32),
@@ -39,13 +38,7 @@ impl Bot {
                         Team::Computer,
                     );
 
-                    t.set_goal(Some(
-                        goals[i / if difficulty == Difficulty::Normal {
-                            1
-                        } else {
-                            2
-                        }],
-                    ));
+                    t.set_goal(Some(goals[(i / coef) % goals.len()]));
                     t
                 })
                 .collect(),
@@ -66,6 +59,10 @@ impl Bot {
             .collect()
     }
 
+    pub fn vision(&self, level: &mut Level) {
+        self.squads.iter().for_each(|squad| squad.vision(level));
+    }
+
     pub fn lose(&self) -> bool {
         self.squads.is_empty()
     }
@@ -73,24 +70,35 @@ impl Bot {
     pub fn update(
         &mut self,
         manager: &Manager,
-        positions: (Vec<IVec2>, Vec<IVec2>, Vec<(IVec2, i16)>),
+        positions: (Vec<IVec2>, Vec<IVec2>, Vec<Attack>, Vec<Hit>),
         level: &mut Level,
         delta: f32,
-    ) -> Vec<(IVec2, i16)> {
+    ) -> (Vec<Attack>, Vec<Hit>) {
         let mut units = self.units();
         let mut deleted = Vec::new();
+        let mut hits = Vec::new();
+
+        for (id, damage, kill) in &positions.3 {
+            if let Some(unit) = units.iter_mut().find(|unit| unit.id == *id) {
+                unit.credit(*damage, *kill);
+            }
+        }
 
-        for pos in &positions.2 {
-            if let Some(i) = units.iter().position(|x| x.start_pos == pos.0) {
-                units[i].health -= pos.1;
-                if units[i].health <= 0 {
-                    deleted.push(pos.0);
+        for (pos, radius, damage, id) in &positions.2 {
+            for unit in units
+                .iter_mut()
+                .filter(|unit| unit.health > 0 && hex::distance(&unit.start_pos, pos) <= *radius)
+            {
+                hits.push((*id, unit.health.min(*damage), unit.health <= *damage));
+                unit.health -= damage;
+                if unit.health <= 0 {
+                    deleted.push(unit.start_pos);
                 }
             }
         }
 
         let mut delete = Vec::new();
-        let mut attacked = Vec::new();
+        let mut attacked = vec![squad::land(&mut self.shells, level, delta).0];
         for (i, squad) in self.squads.iter_mut().enumerate() {
             if let Some(goal) = squad.goal() {
                 let path = level.find_path(&positions.0, squad.start_pos(), goal);
@@ -101,11 +109,10 @@ impl Bot {
                 squad.set_path(manager, path);
             }
 
-            attacked.push(
-                squad
-                    .update(manager, &deleted, &positions.1, level, delta, true)
-                    .0,
-            );
+            let (squad_attacked, shells, _, _) =
+                squad.update(manager, &deleted, &positions.1, level, delta, true);
+            attacked.push(squad_attacked);
+            self.shells.extend(shells);
 
             if squad.empty() {
                 delete.push(i);
@@ -116,6 +123,6 @@ impl Bot {
             self.squads.remove(i);
         }
 
-        attacked.concat()
+        (attacked.concat(), hits)
     }
 }
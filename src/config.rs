@@ -11,6 +11,7 @@ pub const FULLSCREEN_FIELD_NAME: &str = "FullScreen";
 pub const ANTIALIASING_FIELD_NAME: &str = "Antialiasing";
 
 pub const SENSITIVITY_FIELD_NAME: &str = "Sensitivity";
+pub const SEED_FIELD_NAME: &str = "Seed";
 
 pub fn gen_graphics() -> Ini {
     Ini::load_from_file(INI_GRAPHICS_NAME).unwrap_or_else(|_| {
@@ -30,7 +31,8 @@ pub fn gen_game() -> Ini {
     Ini::load_from_file(INI_GAME_NAME).unwrap_or_else(|_| {
         let mut file = Ini::new();
         file.with_general_section()
-            .set(SENSITIVITY_FIELD_NAME, "100");
+            .set(SENSITIVITY_FIELD_NAME, "100")
+            .set(SEED_FIELD_NAME, "");
         #[cfg(not(target_arch = "wasm32"))]
         file.write_to_file(INI_GAME_NAME).unwrap();
         file
@@ -0,0 +1,242 @@
+use std::path::PathBuf;
+
+use macroquad::{
+    camera::{set_camera, set_default_camera, Camera2D},
+    input::{
+        is_key_down, is_key_pressed, is_mouse_button_down, is_mouse_button_pressed,
+        mouse_delta_position, mouse_position, mouse_wheel, KeyCode, MouseButton,
+    },
+    logging::{error, info},
+    math::{vec2, IVec2, Vec2},
+};
+
+use crate::{
+    camera,
+    config::{gen_game, INI_GAME_ERROR, SENSITIVITY_FIELD_NAME},
+    game::DEFAULT_CAMERA_ZOOM,
+    hex,
+    level::{Level, MAPS_DIR, MAP_EXTENSION},
+    manager::Manager,
+    menu::Menu,
+    structs::{ControlKind, Team, Tile, TileType, BRUSH_COLOR, FONT_COLOR},
+};
+
+const MAX_UNDO: usize = 100;
+const MAX_BRUSH: u32 = 4;
+const MAX_CAMERA_ZOOM: u8 = 8;
+const MIN_CAMERA_ZOOM: u8 = 1;
+
+const PALETTE: [(usize, Option<TileType>); 9] = [
+    (23, Some(TileType::Wall)),
+    (65, Some(TileType::Road)),
+    (47, Some(TileType::Water)),
+    (58, Some(TileType::Shallow)),
+    (48, Some(TileType::Mountain(1))),
+    (49, Some(TileType::Mountain(2))),
+    (50, Some(TileType::Mountain(3))),
+    (51, Some(TileType::Control(None))),
+    (52, None),
+];
+
+type Stroke = Vec<(IVec2, Option<Tile>, Option<ControlKind>)>;
+
+pub struct Editor {
+    stroke: Option<Stroke>,
+    path: Option<PathBuf>,
+    status: Option<String>,
+    undo: Vec<Stroke>,
+    sensitivity: f32,
+    camera: Camera2D,
+    paint: usize,
+    level: Level,
+    brush: u32,
+    zoom: Vec2,
+    scale: u8,
+}
+
+impl Editor {
+    pub fn new(mut level: Level, path: Option<PathBuf>) -> Self {
+        let mut config = gen_game();
+        let config = config.with_general_section();
+        let base = level.base(&Team::Player);
+        let (x, y) = Level::convert(base.x as f32, base.y as f32);
+        let (camera, zoom) = camera(vec2(x, y));
+        level.reveal();
+
+        Self {
+            sensitivity: config
+                .get(SENSITIVITY_FIELD_NAME)
+                .expect(INI_GAME_ERROR)
+                .parse::<f32>()
+                .expect(INI_GAME_ERROR),
+            scale: DEFAULT_CAMERA_ZOOM,
+            undo: Vec::new(),
+            stroke: None,
+            status: None,
+            paint: 0,
+            brush: 0,
+            camera,
+            level,
+            path,
+            zoom,
+        }
+    }
+
+    fn commit(&mut self, stroke: Stroke) {
+        if !stroke.is_empty() {
+            self.undo.push(stroke);
+            if self.undo.len() > MAX_UNDO {
+                self.undo.remove(0);
+            }
+        }
+    }
+
+    fn save(&mut self, manager: &Manager) {
+        let path = self.path.clone().unwrap_or_else(|| {
+            (1..)
+                .map(|i| PathBuf::from(MAPS_DIR).join(format!("map_{}.{}", i, MAP_EXTENSION)))
+                .find(|path| !path.exists())
+                .unwrap()
+        });
+
+        match self.level.save(&path) {
+            Ok(()) => {
+                info!("Map saved to {}", path.display());
+                self.status = Some(format!("{}: {}", manager.get_text(57), path.display()));
+                self.path = Some(path);
+            }
+            Err(err) => {
+                error!("{}", err);
+                self.status = Some(err);
+            }
+        }
+    }
+
+    pub fn update(&mut self, manager: &Manager, menu: &Menu) -> bool {
+        let mouse = mouse_position();
+
+        let mut closed = false;
+        if is_mouse_button_down(MouseButton::Middle) {
+            self.camera.target += mouse_delta_position() * self.sensitivity;
+            closed = true;
+        }
+
+        let wheel = mouse_wheel().1;
+        if wheel < 0.0 && self.scale > MIN_CAMERA_ZOOM {
+            self.scale -= 1;
+        } else if wheel > 0.0 && self.scale < MAX_CAMERA_ZOOM {
+            self.scale += 1;
+        }
+        self.camera.zoom = self.zoom * (self.scale + 1) as f32;
+
+        set_camera(&self.camera);
+        self.level.update(&self.camera);
+
+        let hex = Level::hex(self.camera.screen_to_world(vec2(mouse.0, mouse.1)));
+        let area = hex::spiral(hex, self.brush);
+        for pos in &area {
+            Level::draw_hex(pos, FONT_COLOR, BRUSH_COLOR);
+        }
+
+        set_default_camera();
+        let mut clicked = false;
+        for (i, (text, _)) in PALETTE.iter().enumerate() {
+            let text = if i == self.paint {
+                format!("> {}", manager.get_text(*text))
+            } else {
+                manager.get_text(*text).to_string()
+            };
+
+            if menu.button(0.0, 1.5 + i as f32 * 1.2, &text) {
+                self.paint = i;
+                clicked = true;
+            }
+        }
+
+        if menu.button(
+            0.0,
+            12.3,
+            &format!("{}: {}", manager.get_text(53), self.brush + 1),
+        ) {
+            self.brush = (self.brush + 1) % MAX_BRUSH;
+            clicked = true;
+        }
+
+        if menu.button(0.0, 13.5, manager.get_text(40))
+            || (is_key_down(KeyCode::LeftControl) && is_key_pressed(KeyCode::Z))
+        {
+            if let Some(stroke) = self.undo.pop() {
+                for (pos, tile, kind) in stroke.into_iter().rev() {
+                    self.level.restore(&pos, tile, kind);
+                }
+            }
+            clicked = true;
+        }
+
+        if menu.button(0.0, 14.7, manager.get_text(56)) {
+            let bases = [
+                self.level.base(&Team::Player),
+                self.level.base(&Team::Computer),
+            ];
+            let stroke = self
+                .level
+                .tiles()
+                .filter(|(pos, _)| !bases.contains(pos))
+                .map(|(pos, tile)| (*pos, Some(tile.clone()), self.level.kind(pos)))
+                .collect::<Stroke>();
+
+            for (pos, _, _) in &stroke {
+                self.level.delete(pos);
+            }
+            self.commit(stroke);
+            clicked = true;
+        }
+
+        if menu.button(0.0, 15.9, manager.get_text(54)) {
+            self.save(manager);
+            clicked = true;
+        }
+
+        if menu.button(0.0, 17.1, manager.get_text(55)) {
+            return true;
+        }
+
+        if let Some(status) = &self.status {
+            manager.draw_text(false, status, 0.1, 1.0);
+        }
+        manager.draw_cursor(closed);
+
+        if is_mouse_button_pressed(MouseButton::Left) && !clicked {
+            self.stroke = Some(Vec::new());
+        }
+
+        if !is_mouse_button_down(MouseButton::Left) {
+            if let Some(stroke) = self.stroke.take() {
+                self.commit(stroke);
+            }
+        } else if let Some(stroke) = &mut self.stroke {
+            let bases = [
+                self.level.base(&Team::Player),
+                self.level.base(&Team::Computer),
+            ];
+
+            let r#type = &PALETTE[self.paint].1;
+            let palette = self.level.biome().palette();
+            for pos in area {
+                let tile = self.level.tile(&pos);
+                if self.level.range(&pos)
+                    && !bases.contains(&pos)
+                    && tile.as_ref().map(|tile| &tile.r#type) != r#type.as_ref()
+                {
+                    stroke.push((pos, tile, self.level.kind(&pos)));
+                    self.level.paint(
+                        &pos,
+                        r#type.clone().map(|r#type| Tile::new(r#type, palette)),
+                    );
+                }
+            }
+        }
+
+        false
+    }
+}
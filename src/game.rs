@@ -1,20 +1,25 @@
+use std::path::{Path, PathBuf};
+
 use macroquad::{
     camera::{set_camera, Camera2D},
     input::{
-        is_mouse_button_down, is_mouse_button_pressed, mouse_delta_position, mouse_position,
-        mouse_wheel, MouseButton,
+        is_key_pressed, is_mouse_button_down, is_mouse_button_pressed, mouse_delta_position,
+        mouse_position, mouse_wheel, KeyCode, MouseButton,
     },
-    math::{vec2, IVec2, Vec2},
+    logging::{error, info},
+    math::{vec2, Vec2},
 };
 
 use crate::{
     bot::Bot,
     config::{gen_game, INI_GAME_ERROR, SENSITIVITY_FIELD_NAME},
-    level::Level,
+    level::{Level, EXPORTS_DIR, HEIGHTMAP_EXTENSION, MAPS_DIR, MAP_EXTENSION},
     manager::Manager,
     menu::Menu,
     player::Player,
+    squad::{Attack, Hit},
     structs::{Difficulty, Team, UnitType},
+    unit::Unit,
 };
 
 pub const DEFAULT_CAMERA_ZOOM: u8 = 7;
@@ -23,12 +28,12 @@ const MIN_CAMERA_ZOOM: u8 = 7;
 
 #[derive(PartialEq)]
 pub enum GameResult {
-    Lose(u32),
-    Win(u32),
+    Lose(u32, Vec<Unit>),
+    Win(u32, Vec<Unit>),
 }
 
 pub struct Game {
-    player_attacked: Vec<(IVec2, i16)>,
+    player_attacked: (Vec<Attack>, Vec<Hit>),
     difficulty: Difficulty,
     bot: Option<Bot>,
     sensitivity: f32,
@@ -42,31 +47,31 @@ pub struct Game {
 }
 
 impl Game {
-    pub fn new(units: Vec<Vec<UnitType>>, difficulty: Difficulty) -> Self {
+    pub fn new(units: Vec<Vec<UnitType>>, difficulty: Difficulty, mut level: Level) -> Self {
         let mut config = gen_game();
         let config = config.with_general_section();
-        let mut level = Level::new();
-        level.capture(&IVec2::ZERO, &Team::Player);
+        let base = level.base(&Team::Player);
+        level.capture(&base, &Team::Player);
 
         Self {
             win_time: if difficulty == Difficulty::Normal {
                 30.0
             } else {
                 60.0
-            },
+            } * level.size().dimensions().win_time,
             enemy_time: if difficulty == Difficulty::Normal {
                 40.0
             } else {
                 20.0
             },
-            player_attacked: Vec::new(),
+            player_attacked: (Vec::new(), Vec::new()),
             sensitivity: config
                 .get(SENSITIVITY_FIELD_NAME)
                 .expect(INI_GAME_ERROR)
                 .parse::<f32>()
                 .expect(INI_GAME_ERROR),
             zoom: DEFAULT_CAMERA_ZOOM,
-            player: Player::new(units),
+            player: Player::new(units, base),
             timer_enemy: 0.0,
             timer_win: 0.0,
             difficulty,
@@ -75,6 +80,33 @@ impl Game {
         }
     }
 
+    pub fn home(&self) -> Vec2 {
+        let base = self.level.base(&Team::Player);
+        let (x, y) = Level::convert(base.x as f32, base.y as f32);
+        vec2(x, y)
+    }
+
+    /// A free file name after the source map or the seed, existing files are never overwritten.
+    fn path(&self, menu: &Menu, dir: &str, extension: &str) -> PathBuf {
+        let name = menu
+            .map
+            .as_deref()
+            .map_or(self.level.seed().unwrap_or(menu.seed).to_string(), |map| {
+                Menu::map_name(map).to_owned()
+            });
+
+        (0..)
+            .map(|i| {
+                Path::new(dir).join(if i == 0 {
+                    format!("{}.{}", name, extension)
+                } else {
+                    format!("{}_{}.{}", name, i, extension)
+                })
+            })
+            .find(|path| !path.exists())
+            .unwrap()
+    }
+
     pub fn update(
         &mut self,
         manager: &Manager,
@@ -93,7 +125,23 @@ impl Game {
         }
 
         if is_mouse_button_down(MouseButton::Right) {
-            camera.target = vec2(0.0, 0.0);
+            camera.target = self.home();
+        }
+
+        if is_key_pressed(KeyCode::F5) {
+            let path = self.path(menu, MAPS_DIR, MAP_EXTENSION);
+            match self.level.save(&path) {
+                Ok(()) => info!("Map saved to {}", path.display()),
+                Err(err) => error!("{}", err),
+            }
+        }
+
+        if is_key_pressed(KeyCode::F6) {
+            let path = self.path(menu, EXPORTS_DIR, HEIGHTMAP_EXTENSION);
+            match self.level.export(&path) {
+                Ok(()) => info!("Map exported to {}", path.display()),
+                Err(err) => error!("{}", err),
+            }
         }
 
         let wheel = mouse_wheel().1;
@@ -107,6 +155,11 @@ impl Game {
 
         set_camera(camera);
         self.level.update(camera);
+        self.level.reset_visible();
+        self.player.vision(&mut self.level);
+        if let Some(bot) = &self.bot {
+            bot.vision(&mut self.level);
+        }
 
         let result = self.player.update(
             menu,
@@ -133,6 +186,12 @@ impl Game {
             }
         }
 
+        for (_, team) in self.level.update_captures(delta) {
+            if team == Team::Player {
+                manager.play_controlled();
+            }
+        }
+
         let control = self.level.control_player();
         self.player.draw_ui(manager, closed, control);
 
@@ -165,7 +224,7 @@ impl Game {
 
             self.timer_win += delta;
             if self.timer_win >= self.win_time {
-                return Some(GameResult::Win(self.player.fine));
+                return Some(GameResult::Win(self.player.fine, self.player.best()));
             }
         } else {
             self.timer_win = 0.0;
@@ -178,7 +237,7 @@ impl Game {
         }
 
         if self.player.lose() {
-            Some(GameResult::Lose(self.player.fine))
+            Some(GameResult::Lose(self.player.fine, self.player.best()))
         } else {
             None
         }
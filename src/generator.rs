@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use macroquad::{
+    math::{ivec2, IVec2},
+    rand::gen_range,
+};
+use pathfinding::directed::astar::astar;
+
+use crate::{
+    biome::Preset,
+    hex,
+    level::Level,
+    structs::{Tile, TileType},
+};
+
+const BLOCK_SIZE: i32 = 5;
+const PLAZA_RADIUS: u32 = 2;
+const ROAD_REUSE_COST: u32 = 3;
+const ROAD_GROUND_COST: u32 = 10;
+const ROAD_HILL_COST: u32 = 10;
+const ROAD_SHALLOW_COST: u32 = 30;
+const ROAD_BRIDGE_COST: u32 = 60;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Symmetry {
+    None,
+    Point,
+    Mirror,
+}
+
+pub const SYMMETRIES: [Symmetry; 3] = [Symmetry::None, Symmetry::Point, Symmetry::Mirror];
+
+impl Symmetry {
+    pub fn mirror(&self, pos: IVec2) -> IVec2 {
+        match self {
+            Symmetry::None => pos,
+            Symmetry::Point => -pos,
+            Symmetry::Mirror => ivec2(pos.y, pos.x),
+        }
+    }
+
+    pub fn pair(&self, pos: IVec2) -> Vec<IVec2> {
+        let mirror = self.mirror(pos);
+        if mirror == pos {
+            vec![pos]
+        } else {
+            vec![pos, mirror]
+        }
+    }
+
+    pub fn text(&self) -> usize {
+        71 + *self as usize
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Symmetry::None => "none",
+            Symmetry::Point => "point",
+            Symmetry::Mirror => "mirror",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        SYMMETRIES
+            .into_iter()
+            .find(|symmetry| symmetry.name() == name)
+    }
+
+    pub fn next(&self) -> Self {
+        SYMMETRIES[(*self as usize + 1) % SYMMETRIES.len()]
+    }
+}
+
+/// Copies one half of the map onto the other, the half is picked by comparing
+/// each hex with its mirror so both sides agree on which one is the original.
+pub fn symmetrize(root: &mut HashMap<IVec2, Tile>, symmetry: Symmetry, radius: i32) {
+    if symmetry == Symmetry::None {
+        return;
+    }
+
+    for x in -radius..radius {
+        for y in -radius..radius {
+            let pos = ivec2(x, y);
+            let mirror = symmetry.mirror(pos);
+
+            if (pos.x, pos.y) < (mirror.x, mirror.y) && Level::within(&mirror, radius) {
+                match root.get(&pos).cloned() {
+                    Some(tile) => root.insert(mirror, tile),
+                    None => root.remove(&mirror),
+                };
+            }
+        }
+    }
+}
+
+pub fn towns(root: &mut HashMap<IVec2, Tile>, controls: &[IVec2], preset: &Preset) {
+    for center in controls {
+        if gen_range(0, 100) < preset.towns {
+            town(root, *center, preset);
+        }
+    }
+}
+
+fn town(root: &mut HashMap<IVec2, Tile>, center: IVec2, preset: &Preset) {
+    let blocks = preset.town_radius as i32 / BLOCK_SIZE + 1;
+    let size = BLOCK_SIZE - 1;
+
+    for pos in hex::spiral(center, preset.town_radius) {
+        if !root
+            .get(&pos)
+            .is_some_and(|hex| matches!(hex.r#type, TileType::Control(_)))
+        {
+            root.remove(&pos);
+        }
+    }
+
+    for bx in -blocks..blocks {
+        for by in -blocks..blocks {
+            let origin = center + ivec2(bx, by) * BLOCK_SIZE + IVec2::ONE;
+            let block = (0..size)
+                .flat_map(|x| (0..size).map(move |y| ivec2(x, y)))
+                .collect::<Vec<IVec2>>();
+
+            if block.iter().any(|pos| {
+                let distance = hex::distance(&(origin + *pos), &center);
+                distance > preset.town_radius || distance <= PLAZA_RADIUS
+            }) {
+                continue;
+            }
+
+            let (door, door_at) = (gen_range(0, 4), gen_range(1, size - 1));
+            let kind = gen_range(0, 10);
+            for pos in block {
+                let edges = [pos.y == 0, pos.x == size - 1, pos.y == size - 1, pos.x == 0];
+                let along = if door % 2 == 0 { pos.x } else { pos.y };
+                let wall = match kind {
+                    0..=4 => edges.iter().any(|edge| *edge) && !(edges[door] && along == door_at),
+                    5..=7 => true,
+                    _ => false,
+                };
+
+                if wall {
+                    root.insert(origin + pos, Tile::new(TileType::Wall, &preset.palette));
+                }
+            }
+        }
+    }
+}
+
+/// Links every control point into a minimum spanning tree and carves
+/// the cheapest route for each link, preferring roads that already exist.
+pub fn roads(
+    root: &mut HashMap<IVec2, Tile>,
+    controls: &[IVec2],
+    preset: &Preset,
+    radius: i32,
+    symmetry: Symmetry,
+) {
+    let mut linked = vec![controls[0]];
+    let mut rest = controls[1..].to_vec();
+
+    while let Some((from, i, to)) = linked
+        .iter()
+        .flat_map(|from| rest.iter().enumerate().map(move |(i, to)| (*from, i, *to)))
+        .min_by_key(|(from, _, to)| hex::distance(from, to))
+    {
+        rest.remove(i);
+        linked.push(to);
+
+        let path = astar(
+            &from,
+            |pos| {
+                hex::neighbours(pos)
+                    .into_iter()
+                    .filter(|hex| Level::within(hex, radius))
+                    .filter_map(|hex| Some((hex, road_cost(root, &hex)?)))
+                    .collect::<Vec<(IVec2, u32)>>()
+            },
+            |pos| hex::distance(pos, &to) * ROAD_REUSE_COST,
+            |pos| *pos == to,
+        );
+
+        for pos in path
+            .map(|path| path.0)
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|pos| symmetry.pair(pos))
+            .filter(|pos| Level::within(pos, radius))
+        {
+            if !root
+                .get(&pos)
+                .is_some_and(|hex| matches!(hex.r#type, TileType::Control(_)))
+            {
+                root.insert(pos, Tile::new(TileType::Road, &preset.palette));
+            }
+        }
+    }
+}
+
+fn road_cost(root: &HashMap<IVec2, Tile>, pos: &IVec2) -> Option<u32> {
+    match root.get(pos).map(|hex| &hex.r#type) {
+        None | Some(TileType::Control(_)) => Some(ROAD_GROUND_COST),
+        Some(TileType::Road) => Some(ROAD_REUSE_COST),
+        Some(TileType::Mountain(height)) => {
+            Some(ROAD_GROUND_COST + ROAD_HILL_COST * *height as u32)
+        }
+        Some(TileType::Shallow) => Some(ROAD_SHALLOW_COST),
+        Some(TileType::Water) => Some(ROAD_BRIDGE_COST),
+        Some(TileType::Wall) => None,
+    }
+}
@@ -0,0 +1,145 @@
+use macroquad::math::{ivec2, IVec2, Vec2};
+
+const EPSILON: f32 = 1e-4;
+
+pub const DIRECTIONS: [IVec2; 6] = [
+    ivec2(1, 0),
+    ivec2(1, -1),
+    ivec2(0, -1),
+    ivec2(-1, 0),
+    ivec2(-1, 1),
+    ivec2(0, 1),
+];
+
+#[derive(Clone, Copy)]
+pub struct Cube {
+    pub q: f32,
+    pub r: f32,
+    pub s: f32,
+}
+
+impl Cube {
+    pub fn new(pos: Vec2) -> Self {
+        Self {
+            q: pos.x,
+            r: pos.y,
+            s: -pos.x - pos.y,
+        }
+    }
+
+    pub fn lerp(a: &IVec2, b: &IVec2, t: f32) -> Self {
+        Self::new(a.as_vec2() + (*b - *a).as_vec2() * t)
+    }
+
+    pub fn nudge(self, sign: f32) -> Self {
+        Self {
+            q: self.q + EPSILON * sign,
+            r: self.r + EPSILON * sign,
+            s: self.s - EPSILON * 2.0 * sign,
+        }
+    }
+
+    pub fn round(&self) -> IVec2 {
+        let (mut q, mut r, s) = (self.q.round(), self.r.round(), self.s.round());
+        let (dq, dr, ds) = ((q - self.q).abs(), (r - self.r).abs(), (s - self.s).abs());
+
+        if dq > dr && dq > ds {
+            q = -r - s;
+        } else if dr > ds {
+            r = -q - s;
+        }
+
+        ivec2(q as i32, r as i32)
+    }
+}
+
+pub fn distance(a: &IVec2, b: &IVec2) -> u32 {
+    let delta = *a - *b;
+    (delta.x.unsigned_abs() + delta.y.unsigned_abs() + (delta.x + delta.y).unsigned_abs()) / 2
+}
+
+pub fn neighbours(pos: &IVec2) -> Vec<IVec2> {
+    DIRECTIONS.iter().map(|p| *p + *pos).collect()
+}
+
+pub fn ring(center: IVec2, radius: u32) -> Vec<IVec2> {
+    if radius == 0 {
+        return vec![center];
+    }
+
+    let mut pos = center + DIRECTIONS[4] * radius as i32;
+    let mut result = Vec::new();
+    for direction in DIRECTIONS {
+        for _ in 0..radius {
+            result.push(pos);
+            pos += direction;
+        }
+    }
+
+    result
+}
+
+pub fn spiral(center: IVec2, radius: u32) -> Vec<IVec2> {
+    (0..=radius).flat_map(|i| ring(center, i)).collect()
+}
+
+/// Pairs of hexes on both sides of the line, they differ only where it runs along an edge.
+pub fn trace(a: &IVec2, b: &IVec2) -> Vec<(IVec2, IVec2)> {
+    let len = distance(a, b);
+    (0..=len)
+        .map(|i| {
+            let cube = Cube::lerp(a, b, i as f32 / len.max(1) as f32);
+            (cube.nudge(1.0).round(), cube.nudge(-1.0).round())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use macroquad::math::vec2;
+
+    #[test]
+    fn distance_diagonals() {
+        assert_eq!(distance(&ivec2(0, 0), &ivec2(1, -1)), 1);
+        assert_eq!(distance(&ivec2(0, 0), &ivec2(3, -3)), 3);
+        assert_eq!(distance(&ivec2(0, 0), &ivec2(1, 1)), 2);
+        assert_eq!(distance(&ivec2(2, -1), &ivec2(-1, 2)), 3);
+    }
+
+    #[test]
+    fn round_near_edges() {
+        assert_eq!(Cube::new(vec2(0.49, 0.0)).round(), ivec2(0, 0));
+        assert_eq!(Cube::new(vec2(0.51, 0.0)).round(), ivec2(1, 0));
+        assert_eq!(Cube::new(vec2(0.6, 0.3)).round(), ivec2(1, 0));
+        assert_eq!(Cube::new(vec2(-0.2, 0.65)).round(), ivec2(0, 1));
+    }
+
+    #[test]
+    fn ring_and_spiral_sizes() {
+        for radius in 1..6 {
+            let ring = ring(ivec2(2, -3), radius);
+            assert_eq!(ring.len(), 6 * radius as usize);
+            assert!(ring
+                .iter()
+                .all(|pos| distance(pos, &ivec2(2, -3)) == radius));
+            assert_eq!(
+                spiral(ivec2(2, -3), radius).len(),
+                1 + 3 * (radius * (radius + 1)) as usize
+            );
+        }
+    }
+
+    #[test]
+    fn trace_along_edge() {
+        let pairs = trace(&ivec2(0, 0), &ivec2(1, 1));
+        assert_eq!(pairs.len(), 3);
+        assert_ne!(pairs[1].0, pairs[1].1);
+        assert!([ivec2(1, 0), ivec2(0, 1)].contains(&pairs[1].0));
+        assert!([ivec2(1, 0), ivec2(0, 1)].contains(&pairs[1].1));
+
+        assert!(trace(&ivec2(0, 0), &ivec2(3, 0))
+            .iter()
+            .all(|(a, b)| a == b));
+    }
+}
@@ -1,177 +1,814 @@
 use std::{
     collections::{HashMap, HashSet},
-    ops::Range,
+    fs, mem,
+    path::{Path, PathBuf},
 };
 
+use image::{ImageReader, Rgb, RgbImage};
 use macroquad::{
     camera::Camera2D,
     color::Color,
     math::{ivec2, vec2, IVec2, Vec2},
     prelude::info,
     rand::{gen_range, srand},
-    shapes::draw_hexagon,
+    shapes::{draw_arc, draw_hexagon},
     window::{screen_height, screen_width},
 };
-use noise::{NoiseFn, Perlin};
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
 use pathfinding::directed::astar::astar;
+use serde_json::{json, Value};
 
-use crate::{seed, structs::*, TILE_SIZE};
+use crate::{
+    biome::Biome,
+    generator::{self, Symmetry},
+    hex::{self, Cube},
+    size::MapSize,
+    structs::*,
+    TILE_SIZE,
+};
 
-pub const LEVEL_SIZE: i32 = 100;
+pub const MAPS_DIR: &str = "maps";
+pub const MAP_EXTENSION: &str = "json";
+pub const HEIGHTMAP_EXTENSION: &str = "png";
+pub const EXPORTS_DIR: &str = "exports";
+const MAP_VERSION: u64 = 3;
 const HEX_HEIGHT: f32 = (TILE_SIZE + BORDER) * 3.0 / 2.0;
-const LEVEL_RANGE: Range<i32> = -LEVEL_SIZE..LEVEL_SIZE;
-const HALF_CLEAN_TERRITORY: i32 = CLEAN_TERRITORY / 2;
 const HEX_WIDTH: f32 = SQRT_3 * (TILE_SIZE + BORDER);
 const BORDERS: f32 = 10.0 * TILE_SIZE;
-const CLEAN_TERRITORY: i32 = 20;
-const BUILDING_CHANCE: i32 = 25;
+const GRASS_COST: u32 = 10;
+const HILL_COST: u32 = 5;
+const SHALLOW_COST: u32 = 20;
+const ROAD_COST: u32 = 6;
+const CARVE_COST: u32 = 100;
+const HEIGHT_VISION: u32 = 2;
+const CONTROL_VISION: u32 = 15;
+const FOG_DIM: f32 = 0.5;
+const CAPTURE_TIME: f32 = 5.0;
+const CAPTURE_DECAY: f32 = 0.5;
+const RADAR_VISION: u32 = 30;
+const DEPOT_RADIUS: u32 = 6;
+const SUPPLY_RADIUS: u32 = 3;
+const FORT_RADIUS: u32 = 2;
+const FORT_HEIGHT: u8 = 2;
+const HQ_CAPTURE_BONUS: f32 = 1.5;
+const WALL_HEIGHT: u8 = 1;
+const LOB_HEIGHT: u8 = 1;
 const SQRT_3: f32 = 1.7320508;
+const MARKER_SATURATION: u8 = 100;
+const WALL_BRIGHTNESS: u8 = 250;
+const MAX_RESEEDS: u64 = 100;
 
 pub struct Level {
     root: HashMap<IVec2, Tile>,
-    visible: HashSet<IVec2>,
-    solid: HashSet<IVec2>,
+    explored: HashSet<IVec2>,
+    visible: HashMap<Team, HashSet<IVec2>>,
+    progress: HashMap<IVec2, (Team, f32)>,
+    presence: HashMap<IVec2, HashSet<Team>>,
+    kinds: HashMap<IVec2, ControlKind>,
+    bases: (IVec2, IVec2),
     controls: f32,
+    biome: Biome,
+    size: MapSize,
+    seed: Option<u64>,
 }
 
 impl Level {
-    pub fn new() -> Self {
-        let seed = seed();
+    /// Generates a map, moving on to the next seeds while the map is unplayable.
+    pub fn new(seed: u64, biome: Biome, size: MapSize, symmetry: Symmetry) -> Result<Self, String> {
+        let preset = biome.preset();
+        for seed in (0..MAX_RESEEDS).map(|i| seed.wrapping_add(i)) {
+            let noise = Fbm::<Perlin>::new(0)
+                .set_octaves(preset.octaves)
+                .set_frequency(preset.frequency)
+                .set_sources(
+                    (0..preset.octaves)
+                        .map(|i| Perlin::new(((seed ^ (seed >> 32)) as u32).wrapping_add(i as u32)))
+                        .collect(),
+                );
+
+            match Self::generate(
+                seed,
+                biome,
+                size,
+                symmetry,
+                |pos| Self::terrain(noise.get([pos.x as f64, pos.y as f64]), biome),
+                None,
+            ) {
+                Ok(level) => return Ok(level),
+                Err(err) => info!("Seed {} is unplayable ({}), reseeding", seed, err),
+            }
+        }
+
+        Err(format!(
+            "No playable map within {} seeds of {}",
+            MAX_RESEEDS, seed
+        ))
+    }
+
+    /// Imports a heightmap or loads a saved map depending on the file extension.
+    pub fn open(
+        path: &Path,
+        seed: u64,
+        biome: Biome,
+        size: MapSize,
+        symmetry: Symmetry,
+    ) -> Result<Self, String> {
+        if path
+            .extension()
+            .is_some_and(|ext| ext == HEIGHTMAP_EXTENSION)
+        {
+            Self::import(path, seed, biome, size, symmetry)
+        } else {
+            Self::load(path)
+        }
+    }
+
+    /// Builds the terrain from a PNG where brightness is height: dark pixels turn into water
+    /// and bright ones into hills using the biome thresholds, pure white is a wall.
+    ///
+    /// The image is stretched over the whole map. Saturated pixels are markers instead of terrain:
+    /// blue is the player spawn, red is the computer spawn and any other colour is a control point.
+    /// Each connected blot of marker pixels becomes one control point at its centre.
+    /// Without markers the control points are placed as on a generated map.
+    pub fn import(
+        path: &Path,
+        seed: u64,
+        biome: Biome,
+        size: MapSize,
+        symmetry: Symmetry,
+    ) -> Result<Self, String> {
+        let error = |text: &str| format!("{}: {}", path.display(), text);
+        let image = ImageReader::open(path)
+            .map_err(|err| error(&err.to_string()))?
+            .decode()
+            .map_err(|err| error(&err.to_string()))?
+            .to_rgb8();
+
+        let radius = size.dimensions().radius;
+        let (width, height) = (image.width() as i32, image.height() as i32);
+        let pixel = |pos: IVec2| {
+            image.get_pixel(
+                ((pos.x + radius) * width / (radius * 2)) as u32,
+                ((pos.y + radius) * height / (radius * 2)) as u32,
+            )
+        };
+
+        let mut markers: HashSet<(i32, i32)> = image
+            .enumerate_pixels()
+            .filter(|(_, _, color)| {
+                let [r, g, b] = color.0;
+                r.max(g).max(b) - r.min(g).min(b) >= MARKER_SATURATION
+            })
+            .map(|(x, y, _)| (x as i32, y as i32))
+            .collect();
+
+        let (mut controls, mut player, mut computer) = (Vec::new(), None, None);
+        let mut placed = HashSet::new();
+        for (x, y, _) in image.enumerate_pixels() {
+            let start = (x as i32, y as i32);
+            if !markers.remove(&start) {
+                continue;
+            }
+
+            let (mut queue, mut pixels) = (vec![start], Vec::new());
+            while let Some((x, y)) = queue.pop() {
+                pixels.push((x, y));
+                for dx in -1..=1 {
+                    for dy in -1..=1 {
+                        if markers.remove(&(x + dx, y + dy)) {
+                            queue.push((x + dx, y + dy));
+                        }
+                    }
+                }
+            }
+
+            let count = pixels.len() as i64;
+            let (mut x, mut y, mut color) = (0, 0, [0u64; 3]);
+            for (px, py) in pixels {
+                x += px as i64;
+                y += py as i64;
+                for (sum, channel) in color
+                    .iter_mut()
+                    .zip(image.get_pixel(px as u32, py as u32).0)
+                {
+                    *sum += channel as u64;
+                }
+            }
+
+            let pos = ivec2(
+                (x / count) as i32 * radius * 2 / width - radius,
+                (y / count) as i32 * radius * 2 / height - radius,
+            );
+            if !placed.insert(pos) {
+                continue;
+            }
+
+            let [r, g, b] = color;
+            let spawn = if b > r && b > g {
+                Some(&mut player)
+            } else if r > g && r > b {
+                Some(&mut computer)
+            } else {
+                None
+            };
+
+            if let Some(spawn) = spawn {
+                if spawn.replace(pos).is_some() {
+                    return Err(error("each spawn must be marked once"));
+                }
+            }
+            controls.push(pos);
+        }
+
+        let layout = match (player, computer) {
+            (Some(player), Some(computer)) => Some((controls, (player, computer))),
+            (None, None) if controls.is_empty() => None,
+            _ => return Err(error("both spawns must be marked")),
+        };
+
+        Self::generate(
+            seed,
+            biome,
+            size,
+            if layout.is_some() {
+                Symmetry::None
+            } else {
+                symmetry
+            },
+            |pos| {
+                let [r, g, b] = pixel(pos).0;
+                if r.max(g).max(b) - r.min(g).min(b) >= MARKER_SATURATION {
+                    None
+                } else if r.min(g).min(b) >= WALL_BRIGHTNESS {
+                    Some(TileType::Wall)
+                } else {
+                    let luma = (r as f64 + g as f64 + b as f64) / 3.0 / u8::MAX as f64;
+                    Self::terrain(luma * 2.0 - 1.0, biome)
+                }
+            },
+            layout,
+        )
+        .map_err(|err| error(&err))
+    }
+
+    fn terrain(height: f64, biome: Biome) -> Option<TileType> {
+        let preset = biome.preset();
+        if height <= preset.water {
+            Some(TileType::Water)
+        } else if height <= preset.shallow {
+            Some(TileType::Shallow)
+        } else {
+            preset
+                .hills
+                .iter()
+                .rposition(|hill| height >= *hill)
+                .map(|hill| TileType::Mountain(hill as u8 + 1))
+        }
+    }
+
+    fn generate(
+        seed: u64,
+        biome: Biome,
+        size: MapSize,
+        symmetry: Symmetry,
+        terrain: impl Fn(IVec2) -> Option<TileType>,
+        layout: Option<(Vec<IVec2>, (IVec2, IVec2))>,
+    ) -> Result<Self, String> {
         info!("Seed: {}", seed);
 
         srand(seed);
-        let noise = Perlin::new(seed as u32);
+        let preset = biome.preset();
+        let dimensions = size.dimensions();
+        let half_clean = dimensions.clean / 2;
+        let palette = &preset.palette;
         let mut root = HashMap::new();
-        let controls = 10;
 
-        for x in LEVEL_RANGE {
-            for y in LEVEL_RANGE {
-                let height = noise.get([x as f64 * 0.1, y as f64 * 0.1]);
+        for x in -dimensions.radius..dimensions.radius {
+            for y in -dimensions.radius..dimensions.radius {
                 let pos = ivec2(x, y);
-
-                if height <= -0.5 {
-                    root.insert(
-                        pos,
-                        Tile {
-                            r#type: TileType::Water,
-                            fill_color: match gen_range(0, 3) {
-                                0 => WATER_COLOR,
-                                1 => WATER_COLOR_ALT1,
-                                _ => WATER_COLOR_ALT2,
-                            },
-                            border_color: WATER_BORDER_COLOR,
-                        },
-                    );
-                } else if (0.4..=0.6).contains(&height) {
-                    root.insert(
-                        pos,
-                        Tile {
-                            r#type: TileType::Mountain,
-                            fill_color: HILL_LOW_COLOR,
-                            border_color: HILL_LOW_BORDER_COLOR,
-                        },
-                    );
-                } else if (0.6..=0.8).contains(&height) {
-                    root.insert(
-                        pos,
-                        Tile {
-                            r#type: TileType::Mountain,
-                            fill_color: HILL_MEDIUM_COLOR,
-                            border_color: HILL_MEDIUM_BORDER_COLOR,
-                        },
-                    );
-                } else if height > 0.8 {
-                    root.insert(
-                        pos,
-                        Tile {
-                            r#type: TileType::Mountain,
-                            fill_color: HILL_HIGH_COLOR,
-                            border_color: HILL_HIGH_BORDER_COLOR,
-                        },
-                    );
-                } else if gen_range(1, 1001) <= BUILDING_CHANCE && Self::range(&pos) {
-                    root.insert(
-                        pos,
-                        Tile {
-                            r#type: TileType::Wall,
-                            fill_color: WALL_COLOR,
-                            border_color: WALL_BORDER_COLOR,
-                        },
-                    );
+                if let Some(r#type) = terrain(pos) {
+                    root.insert(pos, Tile::new(r#type, palette));
                 }
             }
         }
 
+        if let Some((controls, bases)) = layout {
+            for pos in controls {
+                root.insert(pos, Tile::new(TileType::Control(None), palette));
+            }
+
+            let mut level = Self::build(root, bases, biome, size, Some(seed));
+            return if level.connect(symmetry) {
+                Ok(level)
+            } else {
+                Err("unreachable control point".to_string())
+            };
+        }
+
         let mut tcontrols = Vec::new();
-        (0..controls).for_each(|i| {
-            let mut far: Vec<bool> = vec![true];
-            let (mut x, mut y) = (0, 0);
-
-            while far.iter().any(|val| *val) {
-                (x, y) = if i != 0 {
-                    (
-                        gen_range(-LEVEL_SIZE / 2, LEVEL_SIZE / 2),
-                        gen_range(-LEVEL_SIZE / 2, LEVEL_SIZE / 2),
-                    )
-                } else {
-                    (0, 0)
-                };
+        while tcontrols.len() < dimensions.controls {
+            let pos = if tcontrols.is_empty() {
+                IVec2::ZERO
+            } else {
+                ivec2(
+                    gen_range(-dimensions.radius / 2, dimensions.radius / 2),
+                    gen_range(-dimensions.radius / 2, dimensions.radius / 2),
+                )
+            };
+
+            let pair = symmetry.pair(pos);
+            if tcontrols.len() + pair.len() > dimensions.controls {
+                break;
+            }
 
-                far = tcontrols
+            if pair.iter().any(|a| {
+                tcontrols
                     .iter()
-                    .map(|pos| Self::distance(&ivec2(x, y), pos) < 35)
-                    .collect();
+                    .chain(pair.iter().filter(|b| *b != a))
+                    .any(|b| hex::distance(a, b) < dimensions.spacing)
+            }) {
+                continue;
             }
 
-            tcontrols.push(ivec2(x, y));
+            for pos in pair {
+                for tx in pos.x - half_clean..pos.x + half_clean {
+                    for ty in pos.y - half_clean..pos.y + half_clean {
+                        root.remove(&ivec2(tx, ty));
+                    }
+                }
 
-            for tx in x - HALF_CLEAN_TERRITORY..x + HALF_CLEAN_TERRITORY {
-                for ty in y - HALF_CLEAN_TERRITORY..y + HALF_CLEAN_TERRITORY {
-                    root.remove(&ivec2(tx, ty));
+                root.insert(pos, Tile::new(TileType::Control(None), palette));
+                tcontrols.push(pos);
+            }
+        }
+
+        let bases = if symmetry == Symmetry::None {
+            (IVec2::ZERO, tcontrols[gen_range(1, tcontrols.len())])
+        } else {
+            let base = *tcontrols
+                .iter()
+                .find(|pos| symmetry.mirror(**pos) != **pos)
+                .ok_or("no control point can be mirrored into a base")?;
+            (base, symmetry.mirror(base))
+        };
+
+        generator::towns(
+            &mut root,
+            &tcontrols
+                .iter()
+                .filter(|pos| **pos != bases.0 && **pos != bases.1)
+                .copied()
+                .collect::<Vec<IVec2>>(),
+            preset,
+        );
+        generator::symmetrize(&mut root, symmetry, dimensions.radius);
+        generator::roads(&mut root, &tcontrols, preset, dimensions.radius, symmetry);
+
+        let mut level = Self::build(root, bases, biome, size, Some(seed));
+        if !level.connect(symmetry) {
+            return Err("unreachable control point".to_string());
+        }
+
+        let canonical: Vec<IVec2> = tcontrols
+            .iter()
+            .filter(|pos| **pos != bases.0 && **pos != bases.1)
+            .filter(|pos| {
+                let mirror = symmetry.mirror(**pos);
+                (mirror.x, mirror.y) >= (pos.x, pos.y)
+            })
+            .copied()
+            .collect();
+        let hq = canonical
+            .iter()
+            .min_by_key(|pos| hex::distance(pos, &IVec2::ZERO))
+            .copied();
+
+        for pos in &canonical {
+            let kind = if Some(*pos) == hq {
+                Some(ControlKind::Hq)
+            } else {
+                [ControlKind::Radar, ControlKind::Depot, ControlKind::Fort]
+                    .get(gen_range(0, 4))
+                    .copied()
+            };
+
+            if let Some(kind) = kind {
+                for pos in symmetry.pair(*pos) {
+                    level.kinds.insert(pos, kind);
                 }
             }
+        }
+
+        Ok(level)
+    }
+
+    fn reachable(&self) -> HashSet<IVec2> {
+        let mut reached = HashSet::new();
+        let mut queue = vec![self.bases.0];
+
+        while let Some(pos) = queue.pop() {
+            for hex in hex::neighbours(&pos) {
+                if self.range(&hex) && self.cost(&hex).is_some() && reached.insert(hex) {
+                    queue.push(hex);
+                }
+            }
+        }
+
+        reached
+    }
+
+    /// The first control point with no reached neighbour.
+    fn unreachable(&self, reached: &HashSet<IVec2>) -> Option<IVec2> {
+        self.find_captures()
+            .into_iter()
+            .find(|pos| !hex::neighbours(pos).iter().any(|hex| reached.contains(hex)))
+    }
 
-            let hex = ivec2(x, y);
-            root.insert(
-                hex,
-                Tile {
-                    r#type: TileType::Control(None),
-                    fill_color: CAPTURE_UNOCCUPIED_COLOR,
-                    border_color: CAPTURE_UNOCCUPIED_BORDER_COLOR,
+    /// Carves through walls and water until every control point,
+    /// including both bases, can be reached from the player base.
+    /// An enclosed player base is carved out first.
+    fn connect(&mut self, symmetry: Symmetry) -> bool {
+        loop {
+            let reached = self.reachable();
+            let Some(control) = self.unreachable(&reached) else {
+                return true;
+            };
+
+            let start = if reached.is_empty() {
+                self.bases.0
+            } else {
+                control
+            };
+
+            let Some((path, _)) = astar(
+                &start,
+                |pos| {
+                    hex::neighbours(pos)
+                        .into_iter()
+                        .filter(|hex| {
+                            self.range(hex)
+                                && !self
+                                    .root
+                                    .get(hex)
+                                    .is_some_and(|hex| matches!(hex.r#type, TileType::Control(_)))
+                        })
+                        .map(|hex| (hex, self.cost(&hex).unwrap_or(CARVE_COST)))
+                        .collect::<Vec<(IVec2, u32)>>()
                 },
-            );
-        });
+                |_| 0,
+                |pos| {
+                    if reached.is_empty() {
+                        self.cost(pos).is_some()
+                    } else {
+                        reached.contains(pos)
+                    }
+                },
+            ) else {
+                return false;
+            };
+
+            let mut carved = false;
+
+            for pos in path
+                .into_iter()
+                .skip(1)
+                .flat_map(|pos| symmetry.pair(pos))
+                .collect::<Vec<IVec2>>()
+            {
+                if !self.range(&pos) {
+                    continue;
+                }
+
+                if let Some(TileType::Water) = self.root.get(&pos).map(|hex| &hex.r#type) {
+                    info!("Carved a ford at {} from {}", pos, start);
+                    self.paint(
+                        &pos,
+                        Some(Tile::new(TileType::Shallow, self.biome.palette())),
+                    );
+                    carved = true;
+                } else if self.cost(&pos).is_none() {
+                    info!("Carved a passage at {} from {}", pos, start);
+                    self.delete(&pos);
+                    carved = true;
+                }
+            }
+
+            if !carved {
+                return false;
+            }
+        }
+    }
 
+    fn build(
+        root: HashMap<IVec2, Tile>,
+        bases: (IVec2, IVec2),
+        biome: Biome,
+        size: MapSize,
+        seed: Option<u64>,
+    ) -> Self {
         Self {
-            root: root.clone(),
-            solid: HashSet::from_iter(
-                root.iter()
-                    .filter(|(_, hex)| hex.r#type != TileType::Water)
-                    .map(|(pos, _)| *pos),
-            ),
-            visible: HashSet::new(),
-            controls: controls as f32,
+            biome,
+            size,
+            controls: root
+                .values()
+                .filter(|hex| matches!(hex.r#type, TileType::Control(_)))
+                .count() as f32,
+            explored: HashSet::new(),
+            visible: HashMap::new(),
+            progress: HashMap::new(),
+            presence: HashMap::new(),
+            kinds: HashMap::new(),
+            bases,
+            seed,
+            root,
         }
     }
 
-    pub fn can_shot(&self, start: &IVec2, end: &IVec2) -> bool {
-        let (start, end) = (start.as_vec2(), end.as_vec2());
-        let direction = end - start;
-        let len = direction.length();
-        let mut i = 0.0;
-
-        while i <= len {
-            if self
-                .solid
-                .contains(&(start + direction * (i / len)).round().as_ivec2())
+    pub fn list() -> Vec<PathBuf> {
+        let mut maps = fs::read_dir(MAPS_DIR)
+            .map(|dir| {
+                dir.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| {
+                        path.extension()
+                            .is_some_and(|ext| ext == MAP_EXTENSION || ext == HEIGHTMAP_EXTENSION)
+                    })
+                    .collect::<Vec<PathBuf>>()
+            })
+            .unwrap_or_default();
+
+        maps.sort();
+        maps
+    }
+
+    /// Writes the map as JSON:
+    /// `{"version": 3, "tiles": [[x, y, "wall"], ...], "controls": [[x, y], ...],
+    /// "spawns": {"player": [x, y], "computer": [x, y]}}`.
+    ///
+    /// Coordinates are axial hexes, tile types are `wall`, `road`, `water`, `shallow_water`,
+    /// `low_hill`, `medium_hill` and `high_hill`, every hex that is not listed is open ground.
+    /// Both spawns must also be listed as controls, owners are not stored. A control may carry
+    /// a third element with its kind: `radar`, `depot`, `fort` or `hq`.
+    /// The optional `"biome"` key picks the palette and defaults to `temperate`,
+    /// the optional `"size"` key is `small`, `medium` or `large` and defaults to `medium`.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut tiles = Vec::new();
+        let mut controls = Vec::new();
+
+        for (pos, hex) in &self.root {
+            if let TileType::Control(_) = hex.r#type {
+                if let Some(kind) = self.kinds.get(pos) {
+                    controls.push(json!([pos.x, pos.y, kind.name()]));
+                } else {
+                    controls.push(json!([pos.x, pos.y]));
+                }
+            } else {
+                tiles.push(json!([pos.x, pos.y, Self::tile_name(&hex.r#type)]));
+            }
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+
+        fs::write(
+            path,
+            json!({
+                "version": MAP_VERSION,
+                "biome": self.biome.name(),
+                "size": self.size.name(),
+                "tiles": tiles,
+                "controls": controls,
+                "spawns": {
+                    "player": [self.bases.0.x, self.bases.0.y],
+                    "computer": [self.bases.1.x, self.bases.1.y],
+                },
+            })
+            .to_string(),
+        )
+        .map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    /// Renders one pixel per hex in the axial layout of the map, so thumbnails line up
+    /// with heightmaps. Control points are stamped over their neighbours with the border
+    /// colour to stay visible, unowned spawns take the colour of their team.
+    pub fn export(&self, path: &Path) -> Result<(), String> {
+        let radius = self.size.dimensions().radius;
+        let palette = self.biome.palette();
+        let pixel = |pos: &IVec2| ((pos.x + radius) as u32, (pos.y + radius) as u32);
+        let mut image = RgbImage::from_pixel(
+            radius as u32 * 2,
+            radius as u32 * 2,
+            Self::rgb(palette.grass),
+        );
+
+        for (pos, hex) in &self.root {
+            if self.range(pos) {
+                let (x, y) = pixel(pos);
+                image.put_pixel(x, y, Self::rgb(hex.fill_color));
+            }
+        }
+
+        for pos in self.find_captures() {
+            let tile = match &self.root[&pos].r#type {
+                TileType::Control(None) if pos == self.bases.0 => {
+                    Tile::new(TileType::Control(Some(Team::Player)), palette)
+                }
+                TileType::Control(None) if pos == self.bases.1 => {
+                    Tile::new(TileType::Control(Some(Team::Computer)), palette)
+                }
+                _ => self.root[&pos].clone(),
+            };
+
+            for tpos in hex::neighbours(&pos) {
+                if self.range(&tpos) {
+                    let (x, y) = pixel(&tpos);
+                    image.put_pixel(x, y, Self::rgb(tile.border_color));
+                }
+            }
+
+            if self.range(&pos) {
+                let (x, y) = pixel(&pos);
+                image.put_pixel(x, y, Self::rgb(tile.fill_color));
+            }
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+
+        image
+            .save(path)
+            .map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    fn rgb(color: Color) -> Rgb<u8> {
+        let [r, g, b, _]: [u8; 4] = color.into();
+        Rgb([r, g, b])
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let error = |text: &str| format!("{}: {}", path.display(), text);
+        let file: Value =
+            serde_json::from_str(&fs::read_to_string(path).map_err(|err| error(&err.to_string()))?)
+                .map_err(|err| error(&err.to_string()))?;
+
+        if !file["version"]
+            .as_u64()
+            .is_some_and(|version| (1..=MAP_VERSION).contains(&version))
+        {
+            return Err(error("unsupported map version"));
+        }
+
+        let pos = |value: &Value| -> Result<IVec2, String> {
+            match (value[0].as_i64(), value[1].as_i64()) {
+                (Some(x), Some(y)) => Ok(ivec2(x as i32, y as i32)),
+                _ => Err(error(&format!("invalid position {}", value))),
+            }
+        };
+
+        let biome = match file["biome"].as_str() {
+            Some(name) => Biome::from_name(name).ok_or(error("unknown biome"))?,
+            None => Biome::Temperate,
+        };
+
+        let size = match file["size"].as_str() {
+            Some(name) => MapSize::from_name(name).ok_or(error("unknown size"))?,
+            None => MapSize::Medium,
+        };
+
+        let palette = biome.palette();
+        let mut root = HashMap::new();
+        for tile in file["tiles"].as_array().ok_or(error("missing tiles"))? {
+            let r#type = tile[2]
+                .as_str()
+                .and_then(Self::tile_type)
+                .ok_or(error(&format!("invalid tile {}", tile)))?;
+            root.insert(pos(tile)?, Tile::new(r#type, palette));
+        }
+
+        let mut kinds = HashMap::new();
+        for control in file["controls"]
+            .as_array()
+            .ok_or(error("missing controls"))?
+        {
+            root.insert(pos(control)?, Tile::new(TileType::Control(None), palette));
+            if let Some(name) = control[2].as_str() {
+                kinds.insert(
+                    pos(control)?,
+                    ControlKind::from_name(name).ok_or(error("unknown control kind"))?,
+                );
+            }
+        }
+
+        let bases = (
+            pos(&file["spawns"]["player"])?,
+            pos(&file["spawns"]["computer"])?,
+        );
+
+        if bases.0 == bases.1 {
+            return Err(error("spawns must be different control points"));
+        }
+
+        for base in [bases.0, bases.1] {
+            if !Self::within(&base, size.dimensions().radius) {
+                return Err(error("spawns must be inside the map"));
+            }
+
+            if !root
+                .get(&base)
+                .is_some_and(|hex| hex.r#type == TileType::Control(None))
             {
-                return false;
+                return Err(error("spawns must be control points"));
             }
+        }
 
-            i += 0.01;
+        let mut level = Self::build(root, bases, biome, size, None);
+        if level.unreachable(&level.reachable()).is_some() {
+            return Err(error("unreachable control point"));
         }
 
-        true
+        level.kinds = kinds;
+        Ok(level)
+    }
+
+    fn tile_name(r#type: &TileType) -> &'static str {
+        match r#type {
+            TileType::Mountain(1) => "low_hill",
+            TileType::Mountain(2) => "medium_hill",
+            TileType::Mountain(_) => "high_hill",
+            TileType::Road => "road",
+            TileType::Shallow => "shallow_water",
+            TileType::Water => "water",
+            _ => "wall",
+        }
+    }
+
+    fn tile_type(name: &str) -> Option<TileType> {
+        match name {
+            "low_hill" => Some(TileType::Mountain(1)),
+            "medium_hill" => Some(TileType::Mountain(2)),
+            "high_hill" => Some(TileType::Mountain(3)),
+            "road" => Some(TileType::Road),
+            "shallow_water" => Some(TileType::Shallow),
+            "water" => Some(TileType::Water),
+            "wall" => Some(TileType::Wall),
+            _ => None,
+        }
+    }
+
+    pub fn biome(&self) -> Biome {
+        self.biome
+    }
+
+    pub fn size(&self) -> MapSize {
+        self.size
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn base(&self, team: &Team) -> IVec2 {
+        match team {
+            Team::Player => self.bases.0,
+            Team::Computer => self.bases.1,
+        }
+    }
+
+    pub fn height(&self, pos: &IVec2) -> u8 {
+        match self.root.get(pos).map(|hex| &hex.r#type) {
+            Some(TileType::Mountain(height)) => *height,
+            _ => 0,
+        }
+    }
+
+    fn cover(&self, pos: &IVec2) -> u8 {
+        match self.root.get(pos).map(|hex| &hex.r#type) {
+            Some(TileType::Mountain(height)) => *height,
+            Some(TileType::Control(_)) if self.kinds.get(pos) == Some(&ControlKind::Fort) => {
+                FORT_HEIGHT
+            }
+            Some(TileType::Wall | TileType::Control(_)) => WALL_HEIGHT,
+            _ => 0,
+        }
+    }
+
+    pub fn can_shot(&self, start: &IVec2, end: &IVec2) -> bool {
+        self.clear(start, end, 0)
+    }
+
+    pub fn can_lob(&self, start: &IVec2, end: &IVec2) -> bool {
+        self.clear(start, end, LOB_HEIGHT)
+    }
+
+    fn clear(&self, start: &IVec2, end: &IVec2, over: u8) -> bool {
+        let height = self.height(start).max(self.height(end)) + over;
+        let trace = hex::trace(start, end);
+        trace
+            .iter()
+            .take(trace.len().saturating_sub(1))
+            .skip(1)
+            .all(|(a, b)| self.cover(a) <= height || self.cover(b) <= height)
     }
 
     pub fn control_player(&self) -> u8 {
@@ -188,67 +825,224 @@ impl Level {
         self.root.contains_key(pos)
     }
 
-    pub fn set(&mut self, pos: &IVec2) {
-        self.root.insert(
-            *pos,
-            Tile {
-                r#type: TileType::Wall,
-                fill_color: WALL_COLOR,
-                border_color: WALL_BORDER_COLOR,
-            },
-        );
+    pub fn tiles(&self) -> impl Iterator<Item = (&IVec2, &Tile)> {
+        self.root.iter()
+    }
+
+    pub fn tile(&self, pos: &IVec2) -> Option<Tile> {
+        self.root.get(pos).cloned()
+    }
 
-        self.solid.insert(*pos);
+    pub fn kind(&self, pos: &IVec2) -> Option<ControlKind> {
+        self.kinds.get(pos).copied()
+    }
+
+    /// Paints the tile back together with the control kind it had.
+    pub fn restore(&mut self, pos: &IVec2, tile: Option<Tile>, kind: Option<ControlKind>) {
+        self.paint(pos, tile);
+        if let Some(kind) = kind {
+            self.kinds.insert(*pos, kind);
+        }
+    }
+
+    pub fn paint(&mut self, pos: &IVec2, tile: Option<Tile>) {
+        self.kinds.remove(pos);
+        let old = if let Some(tile) = tile {
+            if let TileType::Control(_) = tile.r#type {
+                self.controls += 1.0;
+            }
+
+            self.root.insert(*pos, tile)
+        } else {
+            self.root.remove(pos)
+        };
+
+        if let Some(Tile {
+            r#type: TileType::Control(_),
+            ..
+        }) = old
+        {
+            self.controls -= 1.0;
+        }
+    }
+
+    pub fn set(&mut self, pos: &IVec2) {
+        self.paint(pos, Some(Tile::new(TileType::Wall, self.biome.palette())));
     }
 
     pub fn delete(&mut self, pos: &IVec2) {
-        self.root.remove(pos);
-        self.solid.remove(pos);
+        self.paint(pos, None);
     }
 
-    pub fn is_visible(&self, pos: &IVec2) -> bool {
-        self.visible.contains(pos)
+    pub fn reveal(&mut self) {
+        let radius = self.size.dimensions().radius;
+        for x in -radius..=radius {
+            for y in -radius..=radius {
+                self.visible(ivec2(x, y), 0, &Team::Player);
+            }
+        }
     }
 
-    pub fn visible(&mut self, pos: IVec2, distance: i32, add: bool) {
-        for dx in -distance..=distance {
-            for dy in -distance..=distance {
-                if (-distance..=distance).contains(&(-dx - dy)) {
-                    let tpos = ivec2(dx, dy) + pos;
-                    if add {
-                        self.visible.insert(tpos);
-                    } else {
-                        self.visible.remove(&tpos);
-                    }
+    pub fn is_visible(&self, pos: &IVec2, team: &Team) -> bool {
+        self.visible
+            .get(team)
+            .is_some_and(|visible| visible.contains(pos))
+    }
+
+    pub fn visible(&mut self, pos: IVec2, distance: u32, team: &Team) {
+        let height = self.height(&pos);
+        let distance = distance + height as u32 * HEIGHT_VISION;
+
+        let mut seen = vec![pos];
+        for end in hex::ring(pos, distance) {
+            for (a, b) in hex::trace(&pos, &end).into_iter().skip(1) {
+                seen.push(a);
+                seen.push(b);
+
+                if self.cover(&a) > height && self.cover(&b) > height {
+                    break;
+                }
+            }
+        }
+
+        let visible = self.visible.entry(team.clone()).or_default();
+        for tpos in seen {
+            if team == &Team::Player {
+                self.explored.insert(tpos);
+            }
+            visible.insert(tpos);
+        }
+    }
+
+    pub fn reset_visible(&mut self) {
+        self.visible.clear();
+        for pos in self.find_captures() {
+            if let TileType::Control(Some(team)) = self.root[&pos].r#type.clone() {
+                if self.kinds.get(&pos) == Some(&ControlKind::Radar) {
+                    self.visible(pos, RADAR_VISION, &team);
+                } else {
+                    self.visible(pos, CONTROL_VISION, &team);
                 }
             }
         }
     }
 
     pub fn capture(&mut self, pos: &IVec2, team: &Team) {
-        let hex = self.root.get_mut(pos).unwrap();
+        self.root.insert(
+            *pos,
+            Tile::new(TileType::Control(Some(team.clone())), self.biome.palette()),
+        );
+    }
 
-        (hex.fill_color, hex.border_color) = match team {
-            Team::Player => (PLAYER_COLOR, PLAYER_BORDER_COLOR),
-            Team::Computer => (ENEMY_COLOR, ENEMY_BORDER_COLOR),
-        };
+    fn near(&self, pos: &IVec2, team: &Team, kind: ControlKind, radius: u32) -> bool {
+        self.kinds.iter().any(|(tpos, tkind)| {
+            *tkind == kind
+                && hex::distance(pos, tpos) <= radius
+                && self.root[tpos].r#type == TileType::Control(Some(team.clone()))
+        })
+    }
+
+    fn owns(&self, team: &Team, kind: ControlKind) -> bool {
+        self.kinds.iter().any(|(pos, tkind)| {
+            *tkind == kind && self.root[pos].r#type == TileType::Control(Some(team.clone()))
+        })
+    }
+
+    pub fn supplied(&self, pos: &IVec2, team: &Team) -> bool {
+        self.near(pos, team, ControlKind::Depot, DEPOT_RADIUS)
+    }
+
+    pub fn resupplied(&self, pos: &IVec2, team: &Team) -> bool {
+        self.supplied(pos, team)
+            || hex::spiral(*pos, SUPPLY_RADIUS).iter().any(|hex| {
+                self.root
+                    .get(hex)
+                    .is_some_and(|hex| hex.r#type == TileType::Control(Some(team.clone())))
+            })
+    }
+
+    pub fn fortified(&self, pos: &IVec2, team: &Team) -> bool {
+        self.near(pos, team, ControlKind::Fort, FORT_RADIUS)
+    }
+
+    pub fn contest(&mut self, pos: &IVec2, team: &Team) {
+        if self
+            .root
+            .get(pos)
+            .is_some_and(|hex| matches!(hex.r#type, TileType::Control(_)))
+        {
+            self.presence.entry(*pos).or_default().insert(team.clone());
+        }
+    }
+
+    /// Advances capture progress of every control point from the squads that contested it
+    /// this frame. A point stalls while both teams are next to it, progress of the other team
+    /// has to be undone first and points left alone slowly decay. Returns captured points.
+    pub fn update_captures(&mut self, delta: f32) -> Vec<(IVec2, Team)> {
+        let mut captured = Vec::new();
+        let presence = mem::take(&mut self.presence);
+        let bonus = [Team::Player, Team::Computer].map(|team| {
+            if self.owns(&team, ControlKind::Hq) {
+                HQ_CAPTURE_BONUS
+            } else {
+                1.0
+            }
+        });
+
+        for pos in self.find_captures() {
+            let teams = presence.get(&pos);
+            let team = match teams {
+                Some(teams) if teams.len() > 1 => continue,
+                Some(teams) => teams
+                    .iter()
+                    .next()
+                    .filter(|team| self.is_capturable(&pos, team)),
+                None => None,
+            };
+
+            match (team, self.progress.get_mut(&pos)) {
+                (Some(team), Some((owner, progress))) if owner == team => {
+                    *progress += delta * bonus[(*team == Team::Computer) as usize];
+                    if *progress >= CAPTURE_TIME {
+                        self.progress.remove(&pos);
+                        self.capture(&pos, team);
+                        captured.push((pos, team.clone()));
+                    }
+                }
+                (Some(_), Some((_, progress))) => {
+                    *progress -= delta;
+                    if *progress <= 0.0 {
+                        self.progress.remove(&pos);
+                    }
+                }
+                (Some(team), None) => {
+                    self.progress.insert(pos, (team.clone(), delta));
+                }
+                (None, Some((_, progress))) => {
+                    *progress -= delta * CAPTURE_DECAY;
+                    if *progress <= 0.0 {
+                        self.progress.remove(&pos);
+                    }
+                }
+                (None, None) => {}
+            }
+        }
 
-        hex.r#type = TileType::Control(Some(team.clone()));
-        self.visible(*pos, 15, team == &Team::Player);
+        captured
     }
 
-    pub fn is_capturable(&self, pos: &IVec2, team: &Team) -> bool {
+    fn is_capturable(&self, pos: &IVec2, team: &Team) -> bool {
         self.root.contains_key(pos)
             && (self.root[pos].r#type == TileType::Control(None)
                 || self.root[pos].r#type == TileType::Control(Some(invert_team(team))))
     }
 
-    pub fn range(pos: &IVec2) -> bool {
-        LEVEL_RANGE.contains(&pos.y) && LEVEL_RANGE.contains(&pos.x)
+    pub fn range(&self, pos: &IVec2) -> bool {
+        Self::within(pos, self.size.dimensions().radius)
     }
 
-    pub fn neighbours(pos: &IVec2) -> Vec<IVec2> {
-        DIRECTIONS.iter().map(|p| *p + *pos).collect()
+    pub fn within(pos: &IVec2, radius: i32) -> bool {
+        (-radius..radius).contains(&pos.y) && (-radius..radius).contains(&pos.x)
     }
 
     pub fn convert(x: f32, y: f32) -> (f32, f32) {
@@ -264,13 +1058,8 @@ impl Level {
     }
 
     pub fn hex(pos: Vec2) -> IVec2 {
-        (vec2((SQRT_3 * pos.x - pos.y) / 3.0, pos.y * 2.0 / 3.0) / (TILE_SIZE + BORDER))
+        Cube::new(vec2((SQRT_3 * pos.x - pos.y) / 3.0, pos.y * 2.0 / 3.0) / (TILE_SIZE + BORDER))
             .round()
-            .as_ivec2()
-    }
-
-    pub fn distance(a: &IVec2, b: &IVec2) -> u32 {
-        a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
     }
 
     pub fn find_captures(&self) -> Vec<IVec2> {
@@ -290,11 +1079,25 @@ impl Level {
         draw_hexagon(x, y, TILE_SIZE, BORDER, true, border, fill);
     }
 
+    pub fn cost(&self, pos: &IVec2) -> Option<u32> {
+        match self.root.get(pos).map(|hex| &hex.r#type) {
+            None => Some(GRASS_COST),
+            Some(TileType::Mountain(height)) => Some(GRASS_COST + HILL_COST * *height as u32),
+            Some(TileType::Shallow) => Some(SHALLOW_COST),
+            Some(TileType::Road) => Some(ROAD_COST),
+            _ => None,
+        }
+    }
+
+    pub fn slowdown(&self, pos: &IVec2) -> f32 {
+        self.cost(pos).unwrap_or(GRASS_COST) as f32 / GRASS_COST as f32
+    }
+
     pub fn find_path(&self, units: &[IVec2], start: IVec2, mut goal: IVec2) -> Vec<IVec2> {
-        if self.root.contains_key(&goal) {
-            let result = Self::neighbours(&goal)
+        if self.cost(&goal).is_none() {
+            let result = hex::neighbours(&goal)
                 .into_iter()
-                .filter(|hex| !self.root.contains_key(hex))
+                .filter(|hex| self.cost(hex).is_some())
                 .collect::<Vec<IVec2>>();
 
             if !result.is_empty() {
@@ -307,42 +1110,74 @@ impl Level {
         astar(
             &start,
             |pos| {
-                Self::neighbours(pos)
+                hex::neighbours(pos)
                     .into_iter()
-                    .filter(|hex| {
-                        Self::range(hex) && !self.root.contains_key(hex) && !units.contains(hex)
-                    })
-                    .map(|hex| (hex, 0))
+                    .filter(|hex| self.range(hex) && !units.contains(hex))
+                    .filter_map(|hex| Some((hex, self.cost(&hex)?)))
                     .collect::<Vec<(IVec2, u32)>>()
             },
-            |pos| Self::distance(pos, &goal) / 2,
+            |pos| hex::distance(pos, &goal) * ROAD_COST,
             |pos| *pos == goal,
         )
         .unwrap_or_default()
         .0
     }
 
-    pub fn update(&mut self, camera: &Camera2D) {
-        for pos in &self.visible {
+    fn dim(color: Color) -> Color {
+        Color::new(
+            color.r + (FOG_COLOR.r - color.r) * FOG_DIM,
+            color.g + (FOG_COLOR.g - color.g) * FOG_DIM,
+            color.b + (FOG_COLOR.b - color.b) * FOG_DIM,
+            color.a,
+        )
+    }
+
+    pub fn update(&self, camera: &Camera2D) {
+        let palette = self.biome.palette();
+        let radius = self.size.dimensions().radius;
+        for pos in &self.explored {
             let (x, y) = Self::convert(pos.x as f32, pos.y as f32);
             let point = camera.world_to_screen(vec2(x, y));
 
             if (-BORDERS..screen_width() + BORDERS).contains(&point.x)
                 && (-BORDERS..screen_height() + BORDERS).contains(&point.y)
             {
-                if self.root.contains_key(pos) {
+                let (border, fill) = if self.root.contains_key(pos) {
                     let hex = &self.root[pos];
-                    Self::draw_hex(pos, hex.border_color, hex.fill_color);
+                    (hex.border_color, hex.fill_color)
+                } else if pos.x.abs() == radius || pos.y.abs() == radius {
+                    (palette.grass, palette.hills[2].1)
                 } else {
-                    Self::draw_hex(
-                        pos,
-                        GRASS_COLOR,
-                        if pos.x.abs() == LEVEL_SIZE || pos.y.abs() == LEVEL_SIZE {
-                            HILL_HIGH_BORDER_COLOR
-                        } else {
-                            GRASS_COLOR
-                        },
-                    );
+                    (palette.grass, palette.grass)
+                };
+
+                let marker = self.kinds.get(pos).map(ControlKind::color);
+                if self.is_visible(pos, &Team::Player) {
+                    Self::draw_hex(pos, border, fill);
+                    if let Some(color) = marker {
+                        draw_hexagon(x, y, TILE_SIZE / 2.0, 0.0, true, color, color);
+                    }
+                    if let Some((team, progress)) = self.progress.get(pos) {
+                        draw_arc(
+                            x,
+                            y,
+                            20,
+                            TILE_SIZE,
+                            0.0,
+                            BORDER,
+                            progress / CAPTURE_TIME * 360.0,
+                            if team == &Team::Player {
+                                PLAYER_BORDER_COLOR
+                            } else {
+                                ENEMY_BORDER_COLOR
+                            },
+                        );
+                    }
+                } else {
+                    Self::draw_hex(pos, Self::dim(border), Self::dim(fill));
+                    if let Some(color) = marker.map(Self::dim) {
+                        draw_hexagon(x, y, TILE_SIZE / 2.0, 0.0, true, color, color);
+                    }
                 }
             }
         }
@@ -1,32 +1,49 @@
 #![windows_subsystem = "windows"]
-use std::io::Cursor;
+use std::{env, io::Cursor, path::Path};
 
+use biome::Biome;
 use config::{
-    gen_graphics, ANTIALIASING_FIELD_NAME, FULLSCREEN_FIELD_NAME, HIGHDPI_FIELD_NAME,
-    INI_GRAPHICS_ERROR,
+    gen_game, gen_graphics, ANTIALIASING_FIELD_NAME, FULLSCREEN_FIELD_NAME, HIGHDPI_FIELD_NAME,
+    INI_GAME_ERROR, INI_GRAPHICS_ERROR, SEED_FIELD_NAME,
 };
+use editor::Editor;
 use game::{Game, GameResult, DEFAULT_CAMERA_ZOOM};
+use generator::Symmetry;
 use image::ImageReader;
-use macroquad::prelude::*;
+use level::{Level, MAP_EXTENSION};
+use macroquad::{prelude::*, Window};
 use manager::Manager;
-use menu::Menu;
-use miniquad::conf::Icon;
+use menu::{Menu, Start};
+use miniquad::{conf::Icon, date};
+use size::MapSize;
 use structs::{Difficulty, FOG_COLOR};
 
+mod biome;
 mod bot;
 mod config;
+mod editor;
 mod game;
+mod generator;
+mod hex;
 mod level;
 mod manager;
 mod menu;
 mod player;
+mod size;
 mod squad;
+mod stats;
 mod structs;
 mod unit;
 
 const WIDTH_TILES: f32 = 400.0;
 const HEIGHT_TILES: f32 = 225.0;
 const TILE_SIZE: f32 = 2.0;
+const SEED_FLAG: &str = "--seed";
+const EXPORT_FLAG: &str = "--export";
+const BIOME_FLAG: &str = "--biome";
+const SIZE_FLAG: &str = "--size";
+const SYMMETRY_FLAG: &str = "--symmetry";
+const MAP_FLAG: &str = "--map";
 
 struct Instance {
     pub game: Game,
@@ -36,6 +53,7 @@ struct Instance {
 
 enum GameState {
     Play(Box<Instance>),
+    Edit(Box<Editor>),
     Init(Difficulty),
     Menu(GameResult),
 }
@@ -134,19 +152,75 @@ fn config() -> Conf {
     }
 }
 
+pub fn camera(target: Vec2) -> (Camera2D, Vec2) {
+    let mut camera = Camera2D::from_display_rect(Rect::new(
+        0.0,
+        0.0,
+        TILE_SIZE * WIDTH_TILES * 2.0,
+        TILE_SIZE * HEIGHT_TILES * 2.0,
+    ));
+
+    camera.target = target;
+    camera.zoom.y *= -1.0;
+    let zoom = camera.zoom;
+    camera.zoom += zoom * DEFAULT_CAMERA_ZOOM as f32;
+    (camera, zoom)
+}
+
+pub fn random_seed() -> u64 {
+    (date::now() * 1000.0) as u64
+}
+
+fn flag(name: &str) -> Option<String> {
+    env::args().skip_while(|arg| arg != name).nth(1)
+}
+
 pub fn seed() -> u64 {
-    // use std::time::{SystemTime, UNIX_EPOCH};
-    // match SystemTime::now().duration_since(UNIX_EPOCH) {
-    //     Ok(n) => n,
-    //     Err(n) => n.duration(),
-    // }
-    // .as_secs()
-    0
+    if let Some(seed) = flag(SEED_FLAG) {
+        return seed
+            .parse()
+            .unwrap_or_else(|_| panic!("The {} flag expects a number", SEED_FLAG));
+    }
+
+    let mut config = gen_game();
+    match config.with_general_section().get(SEED_FIELD_NAME) {
+        Some(seed) if !seed.is_empty() => seed.parse().expect(INI_GAME_ERROR),
+        _ => random_seed(),
+    }
 }
 
-#[macroquad::main(config)]
-async fn main() {
-    let mut state = GameState::Menu(GameResult::Lose(0));
+fn export(path: &str) {
+    let biome = flag(BIOME_FLAG).map_or(Biome::Temperate, |name| {
+        Biome::from_name(&name).unwrap_or_else(|| panic!("Unknown biome {}", name))
+    });
+    let size = flag(SIZE_FLAG).map_or(MapSize::Medium, |name| {
+        MapSize::from_name(&name).unwrap_or_else(|| panic!("Unknown map size {}", name))
+    });
+
+    let symmetry = flag(SYMMETRY_FLAG).map_or(Symmetry::None, |name| {
+        Symmetry::from_name(&name).unwrap_or_else(|| panic!("Unknown symmetry {}", name))
+    });
+
+    if let Some(map) = flag(MAP_FLAG) {
+        Level::open(Path::new(&map), seed(), biome, size, symmetry)
+    } else {
+        Level::new(seed(), biome, size, symmetry)
+    }
+    .and_then(|level| level.export(Path::new(path)))
+    .unwrap_or_else(|err| panic!("{}", err));
+}
+
+fn main() {
+    if let Some(path) = flag(EXPORT_FLAG) {
+        export(&path);
+    } else {
+        Window::from_config(config(), run());
+    }
+}
+
+async fn run() {
+    stats::load();
+    let mut state = GameState::Menu(GameResult::Lose(0, Vec::new()));
     let mut manager = Manager::new("English", screen_width() / WIDTH_TILES / TILE_SIZE).await;
     let mut menu = Menu::new(&manager);
 
@@ -155,12 +229,31 @@ async fn main() {
 
         menu.game_timer += get_frame_time() as f64;
         state = match state {
-            GameState::Menu(result) => {
-                if let Some(difficulty) = menu.update(&mut manager, &result) {
+            GameState::Menu(result) => match menu.update(&mut manager, &result) {
+                Some(Start::Game(difficulty)) => {
                     manager.stop_music();
                     GameState::Init(difficulty)
+                }
+                Some(Start::Editor) => match menu.level() {
+                    Ok(level) => GameState::Edit(Box::new(Editor::new(
+                        level,
+                        menu.map.clone().filter(|path| {
+                            path.extension().is_some_and(|ext| ext == MAP_EXTENSION)
+                        }),
+                    ))),
+                    Err(err) => {
+                        error!("{}", err);
+                        GameState::Menu(result)
+                    }
+                },
+                None => GameState::Menu(result),
+            },
+            GameState::Edit(mut editor) => {
+                if editor.update(&manager, &menu) {
+                    menu.refresh();
+                    GameState::Menu(GameResult::Lose(0, Vec::new()))
                 } else {
-                    GameState::Menu(result)
+                    GameState::Edit(editor)
                 }
             }
             GameState::Play(mut instance) => {
@@ -177,25 +270,20 @@ async fn main() {
                     GameState::Play(instance)
                 }
             }
-            GameState::Init(difficulty) => {
-                let mut camera = Camera2D::from_display_rect(Rect::new(
-                    0.0,
-                    0.0,
-                    TILE_SIZE * WIDTH_TILES * 2.0,
-                    TILE_SIZE * HEIGHT_TILES * 2.0,
-                ));
-
-                camera.target = vec2(0.0, 0.0);
-                camera.zoom.y *= -1.0;
-                let zoom = camera.zoom;
-                camera.zoom += zoom * DEFAULT_CAMERA_ZOOM as f32;
-
-                GameState::Play(Box::new(Instance {
-                    zoom,
-                    camera,
-                    game: Game::new(menu.get_units(), difficulty),
-                }))
-            }
+            GameState::Init(difficulty) => match menu.level() {
+                Ok(level) => {
+                    let game = Game::new(menu.get_units(), difficulty, level);
+                    let (camera, zoom) = camera(game.home());
+
+                    GameState::Play(Box::new(Instance { zoom, camera, game }))
+                }
+                Err(err) => {
+                    error!("{}", err);
+                    menu.restart();
+                    manager.start_music();
+                    GameState::Menu(GameResult::Lose(0, Vec::new()))
+                }
+            },
         };
 
         next_frame().await
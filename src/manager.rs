@@ -190,28 +190,35 @@ impl Manager {
             _ => &self.fire_sound5,
         });
 
-        if !turret && gen_range(0, 101) <= 10 {
-            play_sound_once(match gen_range(1, 3) {
-                1 => &self.reloading_sound1,
-                _ => &self.reloading_sound2,
-            });
-        }
-
         if !turret && gen_range(0, 100) <= 10 {
-            play_sound_once(match gen_range(0, 9) {
+            play_sound_once(match gen_range(0, 8) {
                 0 => &self.cover_me_voice,
                 1 => &self.fire_in_the_hole_voice,
                 2 => &self.get_down_voice,
                 3 => &self.look_out_voice,
                 4 => &self.medic_voice,
-                5 => &self.reloading_voice,
-                6 => &self.sniper_voice,
-                7 => &self.suppressing_fire_voice,
+                5 => &self.sniper_voice,
+                6 => &self.suppressing_fire_voice,
                 _ => &self.watch_my_back_voice,
             });
         }
     }
 
+    pub fn play_reload(&self, voice: bool) {
+        play_sound_once(match gen_range(1, 3) {
+            1 => &self.reloading_sound1,
+            _ => &self.reloading_sound2,
+        });
+
+        if voice && gen_range(0, 100) <= 10 {
+            play_sound_once(&self.reloading_voice);
+        }
+    }
+
+    pub fn play_grenade(&self) {
+        play_sound_once(&self.fire_in_the_hole_voice);
+    }
+
     pub fn play_start_go(&self) {
         play_sound_once(&self.go_voice);
     }
@@ -1,4 +1,8 @@
+use std::path::{Path, PathBuf};
+
 use macroquad::{
+    input::{clear_input_queue, get_char_pressed, is_key_pressed, KeyCode},
+    logging::error,
     math::vec2,
     rand::gen_range,
     text::measure_text,
@@ -7,13 +11,27 @@ use macroquad::{
 };
 
 use crate::{
+    biome::Biome,
     game::GameResult,
+    generator::Symmetry,
+    level::Level,
     manager::{Manager, END_Y_TEXT},
+    random_seed, seed,
+    size::MapSize,
     structs::{Difficulty, UnitType, FOG_COLOR, FONT_COLOR, HOVERED_COLOR},
 };
 
+const SEED_LENGTH: usize = 19;
+const MAPS_SHOWN: usize = 5;
+
+pub enum Start {
+    Game(Difficulty),
+    Editor,
+}
+
 enum MenuState {
     Construct(Difficulty),
+    Setup(Difficulty),
     Difficulty,
     End(usize),
     Language,
@@ -21,7 +39,14 @@ enum MenuState {
 
 pub struct Menu {
     pub game_timer: f64,
+    pub map: Option<PathBuf>,
+    pub seed: u64,
+    biome: Biome,
+    size: MapSize,
+    symmetry: Symmetry,
+    maps: Vec<PathBuf>,
     army: Vec<Vec<UnitType>>,
+    seed_text: String,
     lang: Option<String>,
     custom_squad: bool,
     state: MenuState,
@@ -61,6 +86,13 @@ impl Menu {
         Self {
             font_size,
             lang: None,
+            seed: 0,
+            biome: Biome::Temperate,
+            size: MapSize::Medium,
+            symmetry: Symmetry::None,
+            map: None,
+            maps: Vec::new(),
+            seed_text: String::new(),
             game_time: 0,
             army: Vec::new(),
             custom_squad: false,
@@ -89,11 +121,49 @@ impl Menu {
         result
     }
 
+    pub fn map_name(map: &Path) -> &str {
+        map.file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+    }
+
+    fn setup(&mut self, difficulty: Difficulty) {
+        clear_input_queue();
+        self.refresh();
+        self.seed_text = seed().to_string();
+        self.state = MenuState::Setup(difficulty);
+    }
+
     pub fn button(&self, x: f32, y: f32, text: &str) -> bool {
         root_ui().button(vec2(screen_width() * x, self.font_size * (0.75 + y)), text)
     }
 
-    pub fn update(&mut self, manager: &mut Manager, result: &GameResult) -> Option<Difficulty> {
+    /// Builds the selected map and remembers the seed it was generated with.
+    pub fn level(&mut self) -> Result<Level, String> {
+        let level = if let Some(path) = &self.map {
+            Level::open(path, self.seed, self.biome, self.size, self.symmetry).or_else(|err| {
+                error!("{}", err);
+                Level::new(self.seed, self.biome, self.size, self.symmetry)
+            })
+        } else {
+            Level::new(self.seed, self.biome, self.size, self.symmetry)
+        }?;
+
+        if let Some(seed) = level.seed() {
+            self.seed = seed;
+        }
+        Ok(level)
+    }
+
+    pub fn restart(&mut self) {
+        self.state = MenuState::Difficulty;
+    }
+
+    pub fn refresh(&mut self) {
+        self.maps = Level::list();
+    }
+
+    pub fn update(&mut self, manager: &mut Manager, result: &GameResult) -> Option<Start> {
         manager.draw_text(false, &self.title, 0.1, 1.5);
         manager.draw_text(false, &self.author, END_Y_TEXT, 1.0);
 
@@ -105,13 +175,105 @@ impl Menu {
                 }
 
                 if self.button_centered(manager, 6.0, manager.get_text(1)) {
-                    self.game_timer = 0.0;
-                    self.state = MenuState::Construct(Difficulty::Normal);
+                    self.setup(Difficulty::Normal);
                 }
 
                 if self.button_centered(manager, 7.5, manager.get_text(2)) {
+                    self.setup(Difficulty::Hard);
+                }
+
+                None
+            }
+            MenuState::Setup(difficulty) => {
+                while let Some(char) = get_char_pressed() {
+                    if char.is_ascii_digit() && self.seed_text.len() < SEED_LENGTH {
+                        self.seed_text.push(char);
+                    }
+                }
+
+                if is_key_pressed(KeyCode::Backspace) {
+                    self.seed_text.pop();
+                }
+
+                manager.draw_text(
+                    true,
+                    &format!("{}: {}_", manager.get_text(42), self.seed_text),
+                    2.5,
+                    1.0,
+                );
+
+                if self.button_centered(manager, 3.7, manager.get_text(43)) {
+                    self.seed_text = random_seed().to_string();
+                }
+
+                if self.button(
+                    0.05,
+                    2.0,
+                    &format!(
+                        "{}: {}",
+                        manager.get_text(59),
+                        manager.get_text(self.biome.text())
+                    ),
+                ) {
+                    self.biome = self.biome.next();
+                }
+
+                if self.button(
+                    0.05,
+                    3.2,
+                    &format!(
+                        "{}: {}",
+                        manager.get_text(66),
+                        manager.get_text(self.size.text())
+                    ),
+                ) {
+                    self.size = self.size.next();
+                }
+
+                if self.button(
+                    0.05,
+                    4.4,
+                    &format!(
+                        "{}: {}",
+                        manager.get_text(70),
+                        manager.get_text(self.symmetry.text())
+                    ),
+                ) {
+                    self.symmetry = self.symmetry.next();
+                }
+
+                manager.draw_text(
+                    true,
+                    &format!(
+                        "{}: {}",
+                        manager.get_text(44),
+                        self.map
+                            .as_deref()
+                            .map_or(manager.get_text(45), Self::map_name)
+                    ),
+                    7.0,
+                    1.0,
+                );
+
+                if self.button_centered(manager, 8.2, manager.get_text(45)) {
+                    self.map = None;
+                }
+
+                for (i, map) in self.maps.iter().take(MAPS_SHOWN).enumerate() {
+                    if self.button_centered(manager, 9.4 + i as f32 * 1.2, Self::map_name(map)) {
+                        self.map = Some(map.clone());
+                    }
+                }
+
+                if self.button_centered(manager, 15.6, manager.get_text(46)) {
+                    self.seed = self.seed_text.parse().unwrap_or_else(|_| random_seed());
+                    return Some(Start::Editor);
+                }
+
+                if self.button_centered(manager, 5.0, manager.get_text(39)) {
+                    self.seed = self.seed_text.parse().unwrap_or_else(|_| random_seed());
                     self.game_timer = 0.0;
-                    self.state = MenuState::Construct(Difficulty::Hard);
+                    self.state = MenuState::Construct(difficulty.clone());
                 }
 
                 None
@@ -143,6 +305,7 @@ impl Menu {
                                     match unit {
                                         UnitType::Sniper => manager.get_text(35),
                                         UnitType::Medic => manager.get_text(34),
+                                        UnitType::Grenadier => manager.get_text(74),
                                         _ => manager.get_text(33),
                                     }
                                 ),
@@ -163,13 +326,13 @@ impl Menu {
                                         UnitType::Scout => manager.get_text(32),
                                         _ => {
                                             text = format!("{}:", manager.get_text(31));
-                                            for (i, r#type) in [
-                                                UnitType::Infantry,
-                                                UnitType::Medic,
-                                                UnitType::Sniper,
+                                            for (text_id, r#type) in [
+                                                (33, UnitType::Infantry),
+                                                (34, UnitType::Medic),
+                                                (35, UnitType::Sniper),
+                                                (74, UnitType::Grenadier),
                                             ]
                                             .iter()
-                                            .enumerate()
                                             {
                                                 let count = squad
                                                     .iter()
@@ -179,7 +342,7 @@ impl Menu {
                                                     text = format!(
                                                         "{} {} ({}x),",
                                                         text,
-                                                        manager.get_text(33 + i),
+                                                        manager.get_text(*text_id),
                                                         count
                                                     );
                                                 }
@@ -215,7 +378,7 @@ impl Menu {
                         } else {
                             let diff = difficulty.clone();
                             self.state = MenuState::End(gen_range(0, 8));
-                            return Some(diff);
+                            return Some(Start::Game(diff));
                         }
                     }
 
@@ -287,7 +450,12 @@ impl Menu {
                         }
                     }
 
-                    if self.button_centered(manager, 6.7, manager.get_text(40)) {
+                    if self.custom_squad && self.button_centered(manager, 6.3, manager.get_text(74))
+                    {
+                        self.army[army_len - 1].push(UnitType::Grenadier);
+                    }
+
+                    if self.button_centered(manager, 7.4, manager.get_text(40)) {
                         if self.custom_squad {
                             if !empty {
                                 self.army[army_len - 1].pop();
@@ -317,8 +485,34 @@ impl Menu {
                     1.0,
                 );
 
-                match *result {
-                    GameResult::Lose(fine) => {
+                manager.draw_text(
+                    true,
+                    &format!("{}: {}", manager.get_text(42), self.seed),
+                    8.0,
+                    1.0,
+                );
+
+                let (GameResult::Lose(fine, best) | GameResult::Win(fine, best)) = result;
+                for (i, unit) in best.iter().enumerate() {
+                    manager.draw_text(
+                        true,
+                        &format!(
+                            "{} ({} {}): {} {}, {} {}",
+                            manager.get_text(unit.r#type.text()),
+                            manager.get_text(76),
+                            unit.rank(),
+                            unit.kills,
+                            manager.get_text(77),
+                            unit.damage,
+                            manager.get_text(78)
+                        ),
+                        9.0 + i as f32,
+                        1.0,
+                    );
+                }
+
+                match result {
+                    GameResult::Lose(..) => {
                         manager.draw_text(true, manager.get_text(19), 1.0, 2.0);
                         manager.draw_text(
                             true,
@@ -334,7 +528,7 @@ impl Menu {
                             1.0,
                         )
                     }
-                    GameResult::Win(fine) => {
+                    GameResult::Win(..) => {
                         manager.draw_text(true, manager.get_text(18), 1.0, 2.0);
                         manager.draw_text(
                             true,
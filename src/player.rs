@@ -1,13 +1,16 @@
+use std::cmp::Reverse;
+
 use macroquad::{
     camera::set_default_camera,
     math::{ivec2, IVec2},
 };
 
 use crate::{
+    hex,
     level::Level,
     manager::Manager,
     menu::Menu,
-    squad::{Action, Squad},
+    squad::{self, Action, Attack, Hit, Shell, Squad},
     structs::{Team, UnitType},
     unit::Unit,
 };
@@ -18,10 +21,14 @@ pub struct Player {
     turrets: i32,
     squads: Vec<Squad>,
     current: Option<usize>,
+    fallen: Vec<Unit>,
+    shells: Vec<Shell>,
 }
 
+const BEST_SOLDIERS: usize = 3;
+
 impl Player {
-    pub fn new(units: Vec<Vec<UnitType>>) -> Self {
+    pub fn new(units: Vec<Vec<UnitType>>, base: IVec2) -> Self {
         Self {
             squads: units
                 .into_iter()
@@ -29,7 +36,7 @@ impl Player {
                 .map(|(i, mut types)| {
                     types.reverse();
                     Squad::new(
-                        ivec2(0, if i == 0 { 1 } else { -(i as i32) }),
+                        base + ivec2(0, if i == 0 { 1 } else { -(i as i32) }),
                         types,
                         Team::Player,
                     )
@@ -38,6 +45,8 @@ impl Player {
             turrets: 0,
             walls: 0,
             current: None,
+            fallen: Vec::new(),
+            shells: Vec::new(),
             fine: 0,
         }
     }
@@ -49,6 +58,20 @@ impl Player {
             .collect()
     }
 
+    /// The soldiers with the most experience, fallen ones included.
+    pub fn best(&mut self) -> Vec<Unit> {
+        let mut units: Vec<Unit> = self.units().into_iter().map(|unit| unit.clone()).collect();
+        units.extend(self.fallen.iter().cloned());
+        units.retain(|unit| unit.experience() > 0);
+        units.sort_by_key(|unit| Reverse(unit.experience()));
+        units.truncate(BEST_SOLDIERS);
+        units
+    }
+
+    pub fn vision(&self, level: &mut Level) {
+        self.squads.iter().for_each(|squad| squad.vision(level));
+    }
+
     pub fn lose(&mut self) -> bool {
         if self.squads.len() == 1 && self.squads[0].engineer() {
             let mut lose = true;
@@ -69,27 +92,40 @@ impl Player {
         &mut self,
         menu: &Menu,
         manager: &Manager,
-        attacked: &Vec<(IVec2, i16)>,
+        attacked: &(Vec<Attack>, Vec<Hit>),
         mut positions: Vec<Vec<IVec2>>,
         level: &mut Level,
         goal: IVec2,
         mut clicked: bool,
         delta: f32,
-    ) -> (Vec<IVec2>, Vec<IVec2>, Vec<(IVec2, i16)>) {
+    ) -> (Vec<IVec2>, Vec<IVec2>, Vec<Attack>, Vec<Hit>) {
         let mut units = self.units();
         let mut deleted = Vec::new();
+        let mut fallen = Vec::new();
+        let mut hits = Vec::new();
         let mut turrets = 0;
         let mut fine = 0;
 
-        for pos in attacked {
-            if let Some(i) = units.iter().position(|x| x.start_pos == pos.0) {
-                units[i].health -= pos.1;
-                if units[i].health <= 0 {
-                    deleted.push(pos.0);
+        for (id, damage, kill) in &attacked.1 {
+            if let Some(unit) = units.iter_mut().find(|unit| unit.id == *id) {
+                unit.credit(*damage, *kill);
+            }
+        }
+
+        for (pos, radius, damage, id) in &attacked.0 {
+            for unit in units
+                .iter_mut()
+                .filter(|unit| unit.health > 0 && hex::distance(&unit.start_pos, pos) <= *radius)
+            {
+                hits.push((*id, unit.health.min(*damage), unit.health <= *damage));
+                unit.health -= damage;
+                if unit.health <= 0 {
+                    deleted.push(unit.start_pos);
+                    fallen.push(unit.clone());
 
-                    fine = match units[i].r#type {
+                    fine = match unit.r#type {
                         UnitType::Engineer => 5,
-                        UnitType::Turret => {
+                        UnitType::Turret | UnitType::Mortar => {
                             turrets -= 1;
                             0
                         }
@@ -99,6 +135,7 @@ impl Player {
             }
         }
 
+        self.fallen.append(&mut fallen);
         self.fine += fine;
         self.turrets += turrets;
         let attack_positions = &positions.concat();
@@ -136,13 +173,18 @@ impl Player {
                     clicked = false;
                 }
 
-                if menu.button(0.0, 15.0, manager.get_text(25)) {
+                if self.turrets < 5 && menu.button(0.0, 15.0, manager.get_text(75)) {
+                    squad.set_action(Action::Mortar);
+                    clicked = false;
+                }
+
+                if menu.button(0.0, 16.0, manager.get_text(25)) {
                     squad.set_action(Action::Destroy);
                     clicked = false;
                 }
 
                 let action = squad.action();
-                if action == Action::Turret && self.turrets >= 5 {
+                if (action == Action::Turret || action == Action::Mortar) && self.turrets >= 5 {
                     squad.set_action(Action::Go);
                 }
 
@@ -152,8 +194,11 @@ impl Player {
             }
         }
 
+        let (landed, demolished) = squad::land(&mut self.shells, level, delta);
+        self.walls = (self.walls - demolished).max(0);
+
         let mut delete = Vec::new();
-        let mut attacked = Vec::new();
+        let mut attacked = vec![landed];
         let positions = positions.concat();
         for (i, squad) in &mut self.squads.iter_mut().enumerate() {
             if let Some(current) = self.current {
@@ -191,9 +236,10 @@ impl Player {
                 },
             );
 
-            self.walls += squad_result.1;
-            self.turrets += squad_result.2;
+            self.walls += squad_result.2;
+            self.turrets += squad_result.3;
             attacked.push(squad_result.0);
+            self.shells.extend(squad_result.1);
 
             if squad.empty() {
                 delete.push(i);
@@ -207,7 +253,7 @@ impl Player {
             self.squads.remove(i);
         }
 
-        (positions, player_units.concat(), attacked.concat())
+        (positions, player_units.concat(), attacked.concat(), hits)
     }
 
     pub fn draw_ui(&self, manager: &Manager, closed: bool, controls: u8) {
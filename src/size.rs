@@ -0,0 +1,74 @@
+#[derive(PartialEq, Clone, Copy)]
+pub enum MapSize {
+    Small,
+    Medium,
+    Large,
+}
+
+pub const SIZES: [MapSize; 3] = [MapSize::Small, MapSize::Medium, MapSize::Large];
+
+pub struct Dimensions {
+    pub radius: i32,
+    pub controls: usize,
+    pub spacing: u32,
+    pub clean: i32,
+    pub squads: usize,
+    pub win_time: f32,
+}
+
+const SMALL: Dimensions = Dimensions {
+    radius: 50,
+    controls: 5,
+    spacing: 15,
+    clean: 14,
+    squads: 5,
+    win_time: 0.5,
+};
+
+const MEDIUM: Dimensions = Dimensions {
+    radius: 100,
+    controls: 10,
+    spacing: 25,
+    clean: 20,
+    squads: 10,
+    win_time: 1.0,
+};
+
+const LARGE: Dimensions = Dimensions {
+    radius: 150,
+    controls: 15,
+    spacing: 28,
+    clean: 24,
+    squads: 14,
+    win_time: 1.5,
+};
+
+impl MapSize {
+    pub fn dimensions(&self) -> &'static Dimensions {
+        match self {
+            MapSize::Small => &SMALL,
+            MapSize::Medium => &MEDIUM,
+            MapSize::Large => &LARGE,
+        }
+    }
+
+    pub fn text(&self) -> usize {
+        67 + *self as usize
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MapSize::Small => "small",
+            MapSize::Medium => "medium",
+            MapSize::Large => "large",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        SIZES.into_iter().find(|size| size.name() == name)
+    }
+
+    pub fn next(&self) -> Self {
+        SIZES[(*self as usize + 1) % SIZES.len()]
+    }
+}
@@ -1,21 +1,69 @@
 use crate::{
+    hex,
     level::Level,
     manager::Manager,
-    structs::{Team, UnitType, BORDER, WALL_BORDER_COLOR},
+    stats,
+    structs::{invert_team, Team, UnitType, BORDER, WALL_BORDER_COLOR},
     unit::{Unit, UNIT_SIZE},
 };
 use macroquad::{
     math::{ivec2, IVec2},
-    shapes::draw_arc,
+    rand::gen_range,
+    shapes::{draw_arc, draw_circle_lines},
 };
 
-const VISIBLE_DISTANCE: i32 = 2;
-const CAPTURE_TIME: f32 = 5.0;
+const HEIGHT_RANGE: u32 = 3;
+const FORT_ARMOR: i16 = 2;
+const GRENADE_TIME: f32 = 1.0;
+const SHELL_TIME: f32 = 2.5;
+const SHELL_SCATTER: u32 = 2;
+
+/// Damage dealt to every enemy within `radius` hexes of `pos` by the unit `id`.
+pub type Attack = (IVec2, u32, i16, u32);
+
+/// Damage dealt by the unit `id` and whether it was a kill.
+pub type Hit = (u32, i16, bool);
+
+/// An attack in flight, the time left until it lands and whether it demolishes walls.
+pub type Shell = (Attack, f32, bool);
+
+/// Lands the shells whose time is up, returns their attacks and the number of demolished walls.
+pub fn land(shells: &mut Vec<Shell>, level: &mut Level, delta: f32) -> (Vec<Attack>, i32) {
+    let mut attacked = Vec::new();
+    let mut demolished = 0;
+    for (attack, timer, demolish) in shells.iter_mut() {
+        *timer -= delta;
+        if *timer <= 0.0 {
+            attacked.push(*attack);
+            if *demolish {
+                for pos in hex::spiral(attack.0, attack.1) {
+                    if level.wall(&pos) {
+                        level.delete(&pos);
+                        demolished += 1;
+                    }
+                }
+            }
+        } else if level.is_visible(&attack.0, &Team::Player) {
+            let (x, y) = Level::convert(attack.0.x as f32, attack.0.y as f32);
+            draw_circle_lines(
+                x,
+                y,
+                UNIT_SIZE * (attack.1 * 2 + 1) as f32,
+                BORDER,
+                WALL_BORDER_COLOR,
+            );
+        }
+    }
+    shells.retain(|(_, timer, _)| *timer > 0.0);
+
+    (attacked, demolished)
+}
 
 #[derive(PartialEq, Clone)]
 pub enum Action {
     Destroy,
     Turret,
+    Mortar,
     Wall,
     Go,
 }
@@ -26,7 +74,6 @@ pub struct Squad {
     soldiers: Vec<Unit>,
     goal: Option<IVec2>,
     orig: Option<IVec2>,
-    timer_capture: f32,
     path: Vec<IVec2>,
     timer_build: f32,
     timer_heal: f32,
@@ -42,15 +89,8 @@ impl Squad {
                 .enumerate()
                 .map(|(dt, r#type)| (ivec2(start.x + dt as i32, start.y), r#type))
                 .rev()
-                .map(|(pos, r#type)| {
-                    Unit::new(
-                        r#type,
-                        pos,
-                        0.1 / if r#type == &UnitType::Scout { 1.2 } else { 1.0 },
-                    )
-                })
+                .map(|(pos, r#type)| Unit::new(r#type, pos))
                 .collect(),
-            timer_capture: 0.0,
             path: Vec::new(),
             timer_build: 0.0,
             timer_heal: 0.0,
@@ -143,12 +183,27 @@ impl Squad {
         } else {
             self.soldiers
                 .iter()
-                .filter(|unit| unit.r#type != UnitType::Turret)
+                .filter(|unit| !unit.r#type.emplacement())
                 .map(|unit| unit.start_pos)
                 .collect()
         }
     }
 
+    /// Reveals what the squad sees, done for every squad before any of them picks a target.
+    pub fn vision(&self, level: &mut Level) {
+        for unit in &self.soldiers {
+            level.visible(
+                unit.start_pos,
+                if unit.r#type == UnitType::Scout {
+                    self.soldiers.len() as u32
+                } else {
+                    1
+                } * stats::get(&unit.r#type).vision,
+                &self.team,
+            );
+        }
+    }
+
     pub fn update(
         &mut self,
         manager: &Manager,
@@ -157,7 +212,7 @@ impl Squad {
         level: &mut Level,
         delta: f32,
         active: bool,
-    ) -> (Vec<(IVec2, i16)>, i32, i32) {
+    ) -> (Vec<Attack>, Vec<Shell>, i32, i32) {
         let mut start_pos = if !self.path.is_empty() {
             Some(self.path[0])
         } else {
@@ -171,14 +226,16 @@ impl Squad {
         }
 
         let (mut created, mut turrets) = (0, 0);
-        let (wall, turret, destroy) = (
+        let (wall, turret, mortar, destroy) = (
             self.action == Some(Action::Wall),
             self.action == Some(Action::Turret),
+            self.action == Some(Action::Mortar),
             self.action == Some(Action::Destroy),
         );
 
         if wall
             || turret
+            || mortar
             || (destroy
                 && if let Some(goal) = self.goal {
                     level.wall(&goal)
@@ -212,7 +269,10 @@ impl Squad {
                             level.delete(&orig);
                         } else if turret {
                             turrets += 1;
-                            self.soldiers.push(Unit::new(&UnitType::Turret, orig, 0.0));
+                            self.soldiers.push(Unit::new(&UnitType::Turret, orig));
+                        } else if mortar {
+                            turrets += 1;
+                            self.soldiers.push(Unit::new(&UnitType::Mortar, orig));
                         }
 
                         self.timer_build = 0.0;
@@ -222,7 +282,7 @@ impl Squad {
             }
 
             if let Some(goal) = self.goal {
-                if Level::neighbours(&self.start_pos()).contains(&goal) {
+                if hex::neighbours(&self.start_pos()).contains(&goal) {
                     self.path.clear();
                     self.orig = self.goal;
                     self.goal = None;
@@ -232,68 +292,100 @@ impl Squad {
         }
 
         self.timer_heal += delta;
-        let heal = if self.timer_heal >= 1.0 {
+        let heal = self.timer_heal >= 1.0;
+        if heal {
             self.timer_heal = 0.0;
-            self.soldiers
-                .iter()
-                .any(|unit| unit.r#type == UnitType::Medic)
-        } else {
-            false
-        };
+        }
+        let medic = self
+            .soldiers
+            .iter()
+            .any(|unit| unit.r#type == UnitType::Medic);
+        let engineer = self.engineer();
+
+        let (mut attacked, mut shells) = (Vec::new(), Vec::new());
 
-        let mut attacked = Vec::new();
-        let soldiers_len = self.soldiers.len() as i32;
         self.soldiers.iter_mut().for_each(|unit| {
-            if self.team == Team::Player {
-                level.visible(
-                    unit.start_pos,
-                    if unit.r#type == UnitType::Scout {
-                        soldiers_len
-                    } else {
-                        1
-                    } * VISIBLE_DISTANCE,
-                    true,
-                );
+            let stats = stats::get(&unit.r#type);
+            if heal
+                && !unit.r#type.emplacement()
+                && (medic || level.supplied(&unit.start_pos, &self.team))
+            {
+                unit.heal();
             }
 
-            if heal && unit.r#type != UnitType::Turret {
-                unit.heal();
+            let voice = self.team == Team::Player && !unit.r#type.emplacement();
+            if heal
+                && (engineer || level.resupplied(&unit.start_pos, &self.team))
+                && unit.resupply()
+            {
+                manager.play_reload(voice);
             }
 
+            unit.reload(delta);
+
             if !positions.is_empty() {
-                let mut positions = positions
+                let target = positions
                     .iter()
-                    .map(|a| (Level::distance(a, &unit.start_pos), *a))
-                    .collect::<Vec<(u32, IVec2)>>();
-                positions.sort_by(|a, b| a.0.cmp(&b.0));
-
-                let pos = positions[0];
-                let sniper = unit.r#type == UnitType::Sniper;
-                let visible = level.is_visible(&pos.1) || sniper;
-                if unit.fire(if visible { Some(pos.1) } else { None }, delta)
-                    && visible
-                    && pos.0 < 15 * if sniper { 2 } else { 1 }
-                    && level.can_shot(&unit.start_pos, &pos.1)
-                {
-                    unit.zero_timer();
-                    attacked.push((
-                        pos.1,
-                        match unit.r#type {
-                            UnitType::Infantry => 15,
-                            UnitType::Engineer => 10,
-                            UnitType::Scout => 12,
-                            UnitType::Medic | UnitType::Turret => 8,
-                            UnitType::Sniper => 50,
-                        },
-                    ));
-                    manager.play_fire(unit.r#type == UnitType::Turret);
+                    .filter(|pos| level.is_visible(pos, &self.team))
+                    .map(|a| (hex::distance(a, &unit.start_pos), *a))
+                    .filter(|a| a.0 >= stats.min_range)
+                    .min_by_key(|a| a.0);
+
+                let fire = unit.fire(target.map(|pos| pos.1), delta);
+                if let (true, Some(pos)) = (fire, target) {
+                    let grenadier = unit.r#type == UnitType::Grenadier;
+                    let mortar = unit.r#type == UnitType::Mortar;
+                    if pos.0 < stats.range + level.height(&unit.start_pos) as u32 * HEIGHT_RANGE
+                        && if grenadier {
+                            level.can_lob(&unit.start_pos, &pos.1)
+                        } else {
+                            mortar || level.can_shot(&unit.start_pos, &pos.1)
+                        }
+                    {
+                        if unit.shoot() {
+                            manager.play_reload(voice);
+                        }
+                        let damage = stats.damage;
+                        let target = if mortar {
+                            let scatter = hex::spiral(
+                                pos.1,
+                                SHELL_SCATTER.saturating_sub(unit.rank() as u32),
+                            );
+                            scatter[gen_range(0, scatter.len())]
+                        } else {
+                            pos.1
+                        };
+                        let attack = (
+                            target,
+                            stats.splash,
+                            if level.fortified(&target, &invert_team(&self.team)) {
+                                damage / FORT_ARMOR
+                            } else {
+                                damage
+                            },
+                            unit.id,
+                        );
+
+                        if grenadier {
+                            shells.push((attack, GRENADE_TIME, false));
+                            if self.team == Team::Player {
+                                manager.play_grenade();
+                            }
+                        } else if mortar {
+                            shells.push((attack, SHELL_TIME, true));
+                            manager.play_fire(true);
+                        } else {
+                            attacked.push(attack);
+                            manager.play_fire(unit.r#type == UnitType::Turret);
+                        }
+                    }
                 }
             }
 
-            if unit.r#type != UnitType::Turret {
+            if !unit.r#type.emplacement() {
                 if let Some(pos) = start_pos {
                     if !self.path.is_empty() {
-                        if unit.update(delta) {
+                        if unit.update(delta, level.slowdown(&unit.end_pos)) {
                             unit.end_pos = pos;
                         }
                     } else {
@@ -304,24 +396,15 @@ impl Squad {
                 }
             }
 
-            Level::neighbours(&unit.start_pos).iter().for_each(|hex| {
-                if level.is_capturable(hex, &self.team) {
-                    self.timer_capture += delta;
-                    if self.timer_capture >= CAPTURE_TIME {
-                        level.capture(hex, &self.team);
-                        if unit.r#type != UnitType::Turret && self.team == Team::Player {
-                            manager.play_controlled();
-                        }
-                        self.timer_capture = 0.0;
-                    }
-                }
-            });
+            hex::neighbours(&unit.start_pos)
+                .iter()
+                .for_each(|hex| level.contest(hex, &self.team));
 
-            if level.is_visible(&unit.start_pos) {
+            if level.is_visible(&unit.start_pos, &Team::Player) {
                 unit.render(&self.team, active);
             }
         });
 
-        (attacked, created, turrets)
+        (attacked, shells, created, turrets)
     }
 }
@@ -0,0 +1,123 @@
+use std::{fs, sync::OnceLock};
+
+use serde_json::Value;
+
+use crate::{size::SIZES, structs::UnitType};
+
+const UNITS_NAME: &str = "units.json";
+const UNITS: [(&str, UnitType); 8] = [
+    ("engineer", UnitType::Engineer),
+    ("infantry", UnitType::Infantry),
+    ("turret", UnitType::Turret),
+    ("scout", UnitType::Scout),
+    ("medic", UnitType::Medic),
+    ("sniper", UnitType::Sniper),
+    ("grenadier", UnitType::Grenadier),
+    ("mortar", UnitType::Mortar),
+];
+/// Leaves room for healing and veterancy to multiply health without overflowing.
+const MAX_HEALTH: i16 = i16::MAX / 4;
+/// Leaves room for a resupply to add a magazine to the ammo.
+const MAX_AMMO: u32 = u32::MAX / 2;
+
+static STATS: OnceLock<Vec<Stats>> = OnceLock::new();
+
+pub struct Stats {
+    pub health: i16,
+    pub speed: f32,
+    pub damage: i16,
+    pub cooldown: f32,
+    pub magazine: u32,
+    pub reload: f32,
+    pub ammo: u32,
+    pub min_range: u32,
+    pub range: u32,
+    pub vision: u32,
+    pub splash: u32,
+}
+
+/// Reads `units.json` from the working directory and falls back to the embedded defaults.
+pub fn load() {
+    let text = fs::read_to_string(UNITS_NAME)
+        .unwrap_or_else(|_| include_str!("../assets/units.json").to_string());
+    let stats =
+        parse(&text).unwrap_or_else(|err| panic!("Check the \"{}\" file: {}", UNITS_NAME, err));
+
+    STATS.get_or_init(|| stats);
+}
+
+fn parse(text: &str) -> Result<Vec<Stats>, String> {
+    let file: Value = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    let units = file.as_object().ok_or("expected an object of units")?;
+
+    if let Some(name) = units
+        .keys()
+        .find(|name| !UNITS.iter().any(|(unit, _)| unit == name))
+    {
+        return Err(format!("unknown unit \"{}\"", name));
+    }
+
+    let max_distance = SIZES
+        .iter()
+        .map(|size| size.dimensions().radius)
+        .max()
+        .unwrap_or_default() as f64;
+
+    UNITS
+        .iter()
+        .map(|(name, r#type)| {
+            let unit = units
+                .get(*name)
+                .ok_or(format!("missing unit \"{}\"", name))?;
+            let number = |field: &str, min: f64, max: f64| {
+                unit[field]
+                    .as_f64()
+                    .filter(|value| (min..=max).contains(value))
+                    .ok_or(format!(
+                        "\"{}.{}\" must be a number from {} to {}",
+                        name, field, min, max
+                    ))
+            };
+            let integer = |field: &str, min: f64, max: f64| {
+                number(field, min, max).and_then(|value| {
+                    if value.fract() == 0.0 {
+                        Ok(value)
+                    } else {
+                        Err(format!("\"{}.{}\" must be a whole number", name, field))
+                    }
+                })
+            };
+
+            let min_range = integer("min_range", 0.0, max_distance)? as u32;
+            let range = integer("range", 0.0, max_distance)? as u32;
+            if min_range >= range {
+                return Err(format!(
+                    "\"{}.min_range\" must be less than \"{}.range\"",
+                    name, name
+                ));
+            }
+
+            Ok(Stats {
+                health: integer("health", 1.0, MAX_HEALTH as f64)? as i16,
+                speed: number(
+                    "speed",
+                    if r#type.emplacement() { 0.0 } else { 0.01 },
+                    f32::MAX as f64,
+                )? as f32,
+                damage: integer("damage", 0.0, i16::MAX as f64)? as i16,
+                cooldown: number("cooldown", 0.01, f32::MAX as f64)? as f32,
+                magazine: integer("magazine", 1.0, MAX_AMMO as f64)? as u32,
+                reload: number("reload", 0.01, f32::MAX as f64)? as f32,
+                ammo: integer("ammo", 0.0, MAX_AMMO as f64)? as u32,
+                min_range,
+                range,
+                vision: integer("vision", 0.0, max_distance)? as u32,
+                splash: integer("splash", 0.0, max_distance)? as u32,
+            })
+        })
+        .collect()
+}
+
+pub fn get(r#type: &UnitType) -> &'static Stats {
+    &STATS.get().expect("Unit stats are not loaded")[r#type.clone() as usize]
+}
@@ -1,8 +1,6 @@
-use macroquad::{
-    color::Color,
-    color_u8,
-    math::{ivec2, IVec2},
-};
+use macroquad::{color::Color, color_u8, rand::gen_range};
+
+use crate::biome::Palette;
 
 pub const BORDER: f32 = 0.2;
 pub const FOG_COLOR: Color = color_u8!(35, 45, 55, 255);
@@ -10,6 +8,7 @@ pub const GRASS_COLOR: Color = color_u8!(102, 153, 51, 255);
 
 pub const FONT_COLOR: Color = color_u8!(226, 233, 233, 255);
 pub const HOVERED_COLOR: Color = color_u8!(180, 190, 190, 255);
+pub const BRUSH_COLOR: Color = color_u8!(226, 233, 233, 80);
 
 pub const WALL_COLOR: Color = color_u8!(80, 80, 80, 255);
 pub const WALL_BORDER_COLOR: Color = color_u8!(60, 60, 60, 255);
@@ -28,6 +27,17 @@ pub const WATER_COLOR_ALT1: Color = color_u8!(37, 160, 196, 255);
 pub const WATER_COLOR_ALT2: Color = color_u8!(17, 140, 176, 255);
 pub const WATER_BORDER_COLOR: Color = color_u8!(18, 120, 155, 255);
 
+pub const ROAD_COLOR: Color = color_u8!(165, 140, 100, 255);
+pub const ROAD_BORDER_COLOR: Color = color_u8!(135, 110, 75, 255);
+
+pub const SHALLOW_COLOR: Color = color_u8!(70, 180, 200, 255);
+pub const SHALLOW_BORDER_COLOR: Color = color_u8!(50, 150, 170, 255);
+
+pub const RADAR_COLOR: Color = color_u8!(80, 220, 230, 255);
+pub const DEPOT_COLOR: Color = color_u8!(90, 200, 110, 255);
+pub const FORT_COLOR: Color = color_u8!(110, 100, 90, 255);
+pub const HQ_COLOR: Color = color_u8!(240, 200, 60, 255);
+
 pub const CAPTURE_UNOCCUPIED_COLOR: Color = color_u8!(210, 210, 210, 255);
 pub const CAPTURE_UNOCCUPIED_BORDER_COLOR: Color = color_u8!(150, 150, 150, 255);
 
@@ -50,15 +60,80 @@ pub struct Tile {
     pub border_color: Color,
 }
 
+impl Tile {
+    pub fn new(r#type: TileType, palette: &Palette) -> Self {
+        let (fill_color, border_color) = match &r#type {
+            TileType::Control(None) => (CAPTURE_UNOCCUPIED_COLOR, CAPTURE_UNOCCUPIED_BORDER_COLOR),
+            TileType::Control(Some(Team::Player)) => (PLAYER_COLOR, PLAYER_BORDER_COLOR),
+            TileType::Control(Some(Team::Computer)) => (ENEMY_COLOR, ENEMY_BORDER_COLOR),
+            TileType::Mountain(height) => palette.hills[(*height).clamp(1, 3) as usize - 1],
+            TileType::Water => (
+                palette.water[gen_range(0, palette.water.len())],
+                palette.water_border,
+            ),
+            TileType::Shallow => palette.shallow,
+            TileType::Road => palette.road,
+            TileType::Wall => palette.wall,
+        };
+
+        Self {
+            r#type,
+            fill_color,
+            border_color,
+        }
+    }
+}
+
 #[derive(PartialEq, Clone)]
 pub enum TileType {
     Control(Option<Team>),
-    Mountain,
+    Mountain(u8),
+    Road,
+    Shallow,
     Water,
     Wall,
 }
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Copy)]
+pub enum ControlKind {
+    Radar,
+    Depot,
+    Fort,
+    Hq,
+}
+
+impl ControlKind {
+    pub fn color(&self) -> Color {
+        match self {
+            ControlKind::Radar => RADAR_COLOR,
+            ControlKind::Depot => DEPOT_COLOR,
+            ControlKind::Fort => FORT_COLOR,
+            ControlKind::Hq => HQ_COLOR,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ControlKind::Radar => "radar",
+            ControlKind::Depot => "depot",
+            ControlKind::Fort => "fort",
+            ControlKind::Hq => "hq",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            ControlKind::Radar,
+            ControlKind::Depot,
+            ControlKind::Fort,
+            ControlKind::Hq,
+        ]
+        .into_iter()
+        .find(|kind| kind.name() == name)
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone)]
 pub enum Team {
     Player,
     Computer,
@@ -72,6 +147,27 @@ pub enum UnitType {
     Scout,
     Medic,
     Sniper,
+    Grenadier,
+    Mortar,
+}
+
+impl UnitType {
+    pub fn emplacement(&self) -> bool {
+        matches!(self, UnitType::Turret | UnitType::Mortar)
+    }
+
+    pub fn text(&self) -> usize {
+        match self {
+            UnitType::Engineer => 30,
+            UnitType::Infantry => 33,
+            UnitType::Turret => 24,
+            UnitType::Scout => 32,
+            UnitType::Medic => 34,
+            UnitType::Sniper => 35,
+            UnitType::Grenadier => 74,
+            UnitType::Mortar => 75,
+        }
+    }
 }
 
 #[derive(PartialEq, Clone)]
@@ -87,12 +183,3 @@ pub fn invert_team(team: &Team) -> Team {
         Team::Player
     }
 }
-
-pub const DIRECTIONS: [IVec2; 6] = [
-    ivec2(1, 0),
-    ivec2(1, -1),
-    ivec2(0, -1),
-    ivec2(-1, 0),
-    ivec2(-1, 1),
-    ivec2(0, 1),
-];
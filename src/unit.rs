@@ -1,12 +1,16 @@
-use std::f32::consts::PI;
+use std::{
+    f32::consts::PI,
+    sync::atomic::{AtomicU32, Ordering},
+};
 
 use macroquad::{
     math::{vec2, IVec2, Vec2},
-    shapes::{draw_circle, draw_circle_lines, draw_line},
+    shapes::{draw_arc, draw_circle, draw_circle_lines, draw_line},
 };
 
 use crate::{
     level::Level,
+    stats,
     structs::{
         Team, UnitType, BORDER, ENEMY_BORDER_COLOR, ENEMY_COLOR, PLAYER_BORDER_COLOR, PLAYER_COLOR,
         SNIPER_ENEMY_BORDER_COLOR, SNIPER_ENEMY_COLOR, SNIPER_PLAYER_BORDER_COLOR,
@@ -20,10 +24,19 @@ const MAX_TIMER: f32 = 5.0;
 const LINE_THICK: f32 = BORDER;
 const LINE_LENGTH: f32 = TILE_SIZE;
 const SCOUT_SIZE: f32 = TILE_SIZE * 0.3;
+const PIP_SIZE: f32 = TILE_SIZE * 0.15;
+const PIP_SPACING: f32 = TILE_SIZE * 0.4;
+const KILL_EXPERIENCE: u32 = 100;
+const RANKS: [u32; 3] = [150, 400, 800];
+const RANK_RATE: f32 = 0.1;
+const RANK_HEALTH: i16 = 10;
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
 
 #[derive(PartialEq, Clone)]
 pub struct Unit {
     pub r#type: UnitType,
+    pub id: u32,
     pub start_pos: IVec2,
     pub end_pos: IVec2,
     pub health: i16,
@@ -33,18 +46,17 @@ pub struct Unit {
     speed: f32,
     angle: f32,
     timer: f32,
+    rounds: u32,
+    ammo: u32,
+    reload: f32,
+    pub kills: u32,
+    pub damage: u32,
 }
 
 impl Unit {
-    pub fn new(r#type: &UnitType, pos: IVec2, speed: f32) -> Self {
-        let health = match r#type {
-            UnitType::Infantry => 100,
-            UnitType::Engineer => 120,
-            UnitType::Turret => 50,
-            UnitType::Scout => 75,
-            UnitType::Medic => 90,
-            UnitType::Sniper => 80,
-        };
+    pub fn new(r#type: &UnitType, pos: IVec2) -> Self {
+        let stats = stats::get(r#type);
+        let health = stats.health;
 
         Self {
             tpos: vec2(pos.x as f32, pos.y as f32),
@@ -55,8 +67,41 @@ impl Unit {
             elapsed: 0.0,
             angle: 0.0,
             r#type: r#type.clone(),
+            speed: 1.0 / stats.speed,
+            rounds: stats.magazine,
+            ammo: stats.ammo,
+            reload: 0.0,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            kills: 0,
+            damage: 0,
             health,
-            speed,
+        }
+    }
+
+    pub fn experience(&self) -> u32 {
+        self.damage + self.kills * KILL_EXPERIENCE
+    }
+
+    pub fn rank(&self) -> u8 {
+        RANKS
+            .iter()
+            .filter(|&&rank| self.experience() >= rank)
+            .count() as u8
+    }
+
+    /// Records damage dealt by this unit, a new rank raises its health.
+    pub fn credit(&mut self, damage: i16, kill: bool) {
+        let rank = self.rank();
+        self.damage += damage as u32;
+        if kill {
+            self.kills += 1;
+        }
+
+        if self.rank() > rank {
+            let health = stats::get(&self.r#type).health;
+            let max_health = health + health * self.rank() as i16 / RANK_HEALTH;
+            self.health += max_health - self.max_health;
+            self.max_health = max_health;
         }
     }
 
@@ -72,14 +117,12 @@ impl Unit {
                     + PI;
             }
 
+            if self.reload > 0.0 || self.rounds == 0 {
+                return false;
+            }
+
             if self.timer
-                >= match self.r#type {
-                    UnitType::Infantry => 1.0,
-                    UnitType::Engineer => 1.2,
-                    UnitType::Scout | UnitType::Turret => 0.9,
-                    UnitType::Medic => 1.5,
-                    UnitType::Sniper => 3.0,
-                }
+                >= stats::get(&self.r#type).cooldown * (1.0 - RANK_RATE * self.rank() as f32)
             {
                 return true;
             } else {
@@ -90,13 +133,43 @@ impl Unit {
         false
     }
 
-    pub fn zero_timer(&mut self) {
+    /// Spends a round and starts reloading once the magazine is empty,
+    /// returns whether a reload has started.
+    pub fn shoot(&mut self) -> bool {
         self.timer = 0.0;
+        self.rounds = self.rounds.saturating_sub(1);
+        self.rounds == 0 && self.start_reload()
+    }
+
+    fn start_reload(&mut self) -> bool {
+        if self.ammo > 0 && self.reload <= 0.0 {
+            self.reload = stats::get(&self.r#type).reload;
+            true
+        } else {
+            false
+        }
     }
 
-    pub fn update(&mut self, delta: f32) -> bool {
+    pub fn reload(&mut self, delta: f32) {
+        if self.reload > 0.0 {
+            self.reload -= delta;
+            if self.reload <= 0.0 {
+                let loaded = (stats::get(&self.r#type).magazine - self.rounds).min(self.ammo);
+                self.rounds += loaded;
+                self.ammo -= loaded;
+            }
+        }
+    }
+
+    pub fn resupply(&mut self) -> bool {
+        let stats = stats::get(&self.r#type);
+        self.ammo = stats.ammo.min(self.ammo + stats.magazine);
+        self.rounds == 0 && self.start_reload()
+    }
+
+    pub fn update(&mut self, delta: f32, slowdown: f32) -> bool {
         self.elapsed += delta;
-        let t = (self.elapsed / self.speed).min(1.0);
+        let t = (self.elapsed / (self.speed * slowdown)).min(1.0);
         self.tpos = self.start_pos.as_vec2() + (self.end_pos - self.start_pos).as_vec2() * t;
 
         if t >= 1.0 {
@@ -111,7 +184,7 @@ impl Unit {
     pub fn render(&self, team: &Team, active: bool) {
         let (x, y) = Level::convert(self.tpos.x, self.tpos.y);
         let sniper = self.r#type == UnitType::Sniper;
-        let turret = self.r#type == UnitType::Turret;
+        let turret = self.r#type.emplacement();
 
         draw_circle(
             x,
@@ -172,13 +245,38 @@ impl Unit {
 
             if self.r#type == UnitType::Infantry {
                 draw_circle(x, y, SCOUT_SIZE, color);
+            } else if matches!(self.r#type, UnitType::Grenadier | UnitType::Mortar) {
+                draw_circle_lines(x, y, SCOUT_SIZE, LINE_THICK, color);
             } else if self.r#type == UnitType::Medic {
                 draw_line(x, y - UNIT_SIZE, x, y + UNIT_SIZE, LINE_THICK, color);
                 draw_line(x - UNIT_SIZE, y, x + UNIT_SIZE, y, LINE_THICK, color);
             }
         }
 
-        if self.r#type != UnitType::Turret && active {
+        if self.reload > 0.0 {
+            draw_arc(
+                x,
+                y,
+                20,
+                UNIT_SIZE + LINE_THICK,
+                0.0,
+                LINE_THICK,
+                (1.0 - self.reload / stats::get(&self.r#type).reload) * 360.0,
+                color,
+            );
+        }
+
+        let rank = self.rank();
+        for i in 0..rank {
+            draw_circle(
+                x + (i as f32 - (rank - 1) as f32 / 2.0) * PIP_SPACING,
+                y - UNIT_SIZE - PIP_SPACING,
+                PIP_SIZE,
+                color,
+            );
+        }
+
+        if !self.r#type.emplacement() && active {
             draw_circle_lines(x, y, UNIT_SIZE, LINE_THICK, color);
         }
     }